@@ -97,6 +97,8 @@ if $CREATE_MINTER; then
           ecdsa_key_name = \"test_key_1\";
           ledger_id = principal \"$(dfx canister id ledger --network="$NETWORK")\";
           minimum_withdrawal_amount = 100;
+          token_decimals = opt 9;
+          max_events_per_tick = opt 50;
         }
       })
     "
@@ -14,11 +14,72 @@ use std::time::Duration;
 // vec![1],
 pub const DERIVATION_PATH: Vec<ByteBuf> = vec![];
 
+// Threshold ECDSA keys available on the IC: the local replica key, the test key and the production key.
+pub const KNOWN_ECDSA_KEY_NAMES: [&str; 3] = ["dfx_test_key", "test_key_1", "key_1"];
+
+// SOL amounts in deposits are denominated in lamports (1 SOL = 10^9 lamports).
+pub const SOL_DECIMALS: u8 = 9;
+pub const MAX_TOKEN_DECIMALS: u8 = 18;
+// Ceiling of minimum_withdrawal_amount in whole tokens, anything above makes withdrawals impossible.
+pub const MAX_MINIMUM_WITHDRAWAL_TOKENS: u64 = 1_000_000;
+
 pub const GET_LATEST_SOLANA_SIGNATURE: Duration = Duration::from_secs(1 * 60);
 pub const SCRAPPING_SOLANA_SIGNATURE_RANGES: Duration = Duration::from_secs(3 * 60);
 pub const SCRAPPING_SOLANA_SIGNATURES: Duration = Duration::from_secs(3 * 60);
 pub const MINT_GSOL: Duration = Duration::from_secs(3 * 60);
 
+// Upper bound of the random delay before the first run of each timer task,
+// so the tasks do not hit the RPC provider at the same time after install/upgrade.
+pub const MAX_TIMER_JITTER: Duration = Duration::from_secs(30);
+
+// Pause of the RPC-calling tasks after the provider rate-limited us (HTTP 429).
+pub const RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(10 * 60);
+
+// Number of consecutive failed ledger calls after which minting is paused.
+pub const MINTING_CIRCUIT_BREAKER_THRESHOLD: u32 = 10;
+// Minting resumes automatically after this pause, or earlier via `resume_minting`.
+pub const MINTING_PAUSE_COOLDOWN: Duration = Duration::from_secs(30 * 60);
+
 pub const SOLANA_SIGNATURE_RANGES_RETRY_LIMIT: u8 = 100;
 pub const SOLANA_SIGNATURE_RETRY_LIMIT: u8 = 100;
 pub const MINT_GSOL_RETRY_LIMIT: u8 = 100;
+
+// Default number of signatures/deposits processed by a single timer tick,
+// bounds the work done per message when a large backlog builds up.
+pub const DEFAULT_MAX_EVENTS_PER_TICK: u64 = 50;
+
+// Default number of the newest signatures requested by a single get_latest_signature call.
+pub const DEFAULT_LATEST_SIGNATURES_BATCH_SIZE: u8 = 5;
+
+// Number of the most recent deposit errors kept in memory for diagnostics.
+pub const MAX_DEPOSIT_ERRORS: usize = 100;
+
+// Upper bound of the pending withdrawals returned by a single query.
+pub const MAX_PENDING_WITHDRAWALS: usize = 1_000;
+
+// Upper bound of the coupons signed by a single generate_all_coupons call,
+// each coupon costs a threshold ECDSA signature.
+pub const MAX_COUPONS_PER_CALL: usize = 10;
+
+// Number of coupon verification results kept in memory.
+pub const VERIFIED_COUPONS_CACHE_SIZE: usize = 256;
+
+// Number of times a signing request rejected with SysTransient is repeated before giving up.
+pub const SIGN_WITH_ECDSA_RETRY_LIMIT: u8 = 3;
+// Delay before the first repeated signing request, doubled for every further one.
+pub const SIGN_WITH_ECDSA_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+// Withdrawal idempotency keys are remembered for this long, a retry after the window burns again.
+pub const WITHDRAWAL_IDEMPOTENCY_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+// Upper bound of the withdrawal idempotency keys kept in memory, the oldest are dropped first.
+pub const MAX_WITHDRAWAL_IDEMPOTENCY_KEYS: usize = 1_000;
+
+// Upper bound of the withdrawals returned by a single get_burn_events_since query.
+pub const MAX_BURN_EVENTS_PAGE_SIZE: usize = 1_000;
+
+// Upper bound of the events returned by a single get_events_paginated query.
+pub const MAX_EVENTS_PAGE_SIZE: usize = 100;
+
+// Upper bound (bytes) of the JSON returned by a single export_events_json query,
+// keeps the response below the 2 MiB message limit.
+pub const MAX_EVENTS_EXPORT_SIZE: usize = 1_500_000;
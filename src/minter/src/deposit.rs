@@ -1,17 +1,22 @@
 use crate::{
     constants::{
-        MINT_GSOL_RETRY_LIMIT, SOLANA_SIGNATURE_RANGES_RETRY_LIMIT, SOLANA_SIGNATURE_RETRY_LIMIT,
+        MINTING_CIRCUIT_BREAKER_THRESHOLD, MINT_GSOL_RETRY_LIMIT,
+        SOLANA_SIGNATURE_RANGES_RETRY_LIMIT, SOLANA_SIGNATURE_RETRY_LIMIT,
     },
-    events::{DepositEvent, DepositEventError, SolanaSignature, SolanaSignatureRange},
+    events::{DepositEvent, DepositEventError, SolanaSignature, SolanaSignatureRange, GENESIS},
     guard::TimerGuard,
     logs::{DEBUG, INFO},
-    sol_rpc_client::{responses::GetTransactionResponse, LedgerMemo, SolRpcClient, SolRpcError},
+    sol_rpc_client::{
+        responses::{GetTransactionResponse, SignatureResponse},
+        LedgerMemo, SolRpcClient, SolRpcError,
+    },
     state::audit::process_event,
-    state::event::EventType,
+    state::event::{EventType, RetryKind},
     state::{mutate_state, read_state, State, TaskType},
-    utils::{HashMapUtils, VecUtils},
+    utils::{MapUtils, VecUtils},
 };
 
+use candid::{CandidType, Nat};
 use icrc_ledger_types::icrc1::transfer::TransferError;
 use num_traits::ToPrimitive;
 use std::collections::HashMap;
@@ -19,13 +24,16 @@ use std::collections::HashMap;
 const GET_SIGNATURES_BY_ADDRESS_LIMIT: u8 = 10;
 const GET_TRANSACTIONS_LIMIT: u8 = 1;
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
 pub enum DepositError {
     RpcCallFailed(SolRpcError),
     SignatureFailed { sig: String, err: SolRpcError },
     SignatureNotFound(String),
     InvalidDepositData(String),
     NonDepositTransaction(String),
+    TransactionFailed { sig: String, err: String },
+    PrunedSlot { sig: String, slot: u64 },
+    AmountAboveMaximum { sig: String, amount: Nat, max: Nat },
     MintingGSolFailed(TransferError),
     SendingMessageToLedgerFailed { id: String, code: i32, msg: String },
     DepositEventFailed { sig: String, err: DepositEventError },
@@ -49,6 +57,18 @@ impl std::fmt::Display for DepositError {
             DepositError::NonDepositTransaction(sig) => {
                 write!(f, "Signature {sig} : non-Deposit transaction found")
             }
+            DepositError::TransactionFailed { sig, err } => {
+                write!(f, "Signature {sig} : transaction failed with {err}")
+            }
+            DepositError::PrunedSlot { sig, slot } => {
+                write!(f, "Signature {sig} : slot {slot} was already pruned")
+            }
+            DepositError::AmountAboveMaximum { sig, amount, max } => {
+                write!(
+                    f,
+                    "Signature {sig} : amount {amount} is above the maximum deposit amount {max}"
+                )
+            }
             DepositError::MintingGSolFailed(err) => {
                 write!(f, "Failed to mint gSOL: {err:?}")
             }
@@ -65,36 +85,102 @@ impl std::fmt::Display for DepositError {
     }
 }
 
-// fetch newest signature and push a new range to the state
+// fetch newest signature of every watched contract and push new ranges to the state
 pub async fn get_latest_signature() {
     let _guard = match TimerGuard::new(TaskType::GetLatestSignature) {
         Ok(guard) => guard,
         Err(_) => return,
     };
 
+    if is_rate_limited() {
+        return;
+    }
+
     ic_canister_log::log!(DEBUG, "\nSearching for new signatures ...");
 
+    let rpc_client = read_state(SolRpcClient::from_state);
+
+    // the chain tip is only informational, a failed call must not block the scrape
+    match rpc_client.get_slot().await {
+        Ok(slot) => mutate_state(|s| s.latest_chain_slot = Some(slot)),
+        Err(error) => ic_canister_log::log!(DEBUG, "\nFailed to get the latest slot: {error:?}"),
+    }
+
+    let address = read_state(|s| s.solana_contract_address.clone());
     let until_signature = read_state(|s| s.get_solana_last_known_signature());
+    if let Some(newest) = get_newest_signature(&rpc_client, &address, Some(&until_signature)).await
+    {
+        process_new_solana_signature_range(&newest, Some(&until_signature), None);
+    }
+
+    for address in read_state(|s| s.additional_solana_contract_addresses.clone()) {
+        let until_signature =
+            read_state(|s| s.additional_last_known_signatures.get(&address).cloned());
+        if let Some(newest) =
+            get_newest_signature(&rpc_client, &address, until_signature.as_ref()).await
+        {
+            // a newly added contract is watched from its newest signature on, without a range
+            process_new_solana_signature_range(&newest, until_signature.as_ref(), Some(&address));
+        }
+    }
+}
+
+// Signatures are returned newest first, the newest one becomes the start of the new range.
+fn newest_signature(signatures: &[SignatureResponse]) -> Option<String> {
+    match SolanaSignature::try_new(&signatures.first()?.signature, None) {
+        Ok(newest_sig) => Some(newest_sig.sol_sig),
+        Err(err) => {
+            ic_canister_log::log!(INFO, "\nIgnoring newest signature: {err}");
+            None
+        }
+    }
+}
+
+// Returns the newest signature of the contract newer than until_signature, if any.
+async fn get_newest_signature(
+    rpc_client: &SolRpcClient,
+    address: &str,
+    until_signature: Option<&String>,
+) -> Option<String> {
+    let batch_size = read_state(|s| s.latest_signatures_batch_size);
+    let until = until_signature.map_or(GENESIS, |s| s.as_str());
 
     // RPC call underneath is exclusive, so until_signature is not included in the result
-    match read_state(SolRpcClient::from_state)
-        .get_signatures_for_address(1, None, &until_signature)
+    match rpc_client
+        .get_signatures_for_address(address, batch_size, None, until_signature)
         .await
     {
-        Ok(signatures) => match signatures.len() {
-            0 => {
-                ic_canister_log::log!(DEBUG, "\nNo new signatures found");
-            }
-            1 => {
-                let newest_sig = signatures[0].signature.to_string();
-                process_new_solana_signature_range(&newest_sig, &until_signature);
+        Ok(signatures) => {
+            mutate_state(|s| s.last_successful_scrape_timestamp = Some(ic_cdk::api::time()));
+
+            if signatures.is_empty() {
+                ic_canister_log::log!(DEBUG, "\nNo new signatures found for {address}");
+                return None;
             }
-            _ => {
-                ic_canister_log::log!(INFO, "\nUnexpected behaviour");
+
+            // a full batch means the provider paginated and more signatures may be pending,
+            // they are still covered because the new range always ends at until_signature
+            let count = signatures.len();
+            if count >= batch_size as usize {
+                ic_canister_log::log!(
+                    INFO,
+                    "\nFound at least {count} new signatures of {address} since {until}"
+                );
+            } else {
+                ic_canister_log::log!(
+                    DEBUG,
+                    "\nFound {count} new signatures of {address} since {until}"
+                );
             }
-        },
+
+            newest_signature(&signatures)
+        }
         Err(error) => {
-            ic_canister_log::log!(INFO, "\nFailed to get signatures for address: {error:?}");
+            ic_canister_log::log!(
+                INFO,
+                "\nFailed to get signatures for address {address}: {error:?}"
+            );
+            None
         }
     }
 }
@@ -105,10 +191,14 @@ pub async fn scrap_signature_range() {
         Err(_) => return,
     };
 
+    if is_rate_limited() {
+        return;
+    }
+
     let rpc_client = read_state(SolRpcClient::from_state);
     // filter out all events that have reached the retry limit
     let filtered_ranges =
-        HashMapUtils::filter(&read_state(|s| s.solana_signature_ranges.clone()), |s| {
+        MapUtils::filter(&read_state(|s| s.solana_signature_ranges.clone()), |s| {
             !s.retry
                 .is_retry_limit_reached(SOLANA_SIGNATURE_RANGES_RETRY_LIMIT)
         });
@@ -116,7 +206,7 @@ pub async fn scrap_signature_range() {
     ic_canister_log::log!(
         DEBUG,
         "\nProcessing ranges:\n{}",
-        HashMapUtils::format_keys_as_string(&filtered_ranges)
+        MapUtils::format_keys_as_string(&filtered_ranges)
     );
 
     for (_, v) in &filtered_ranges {
@@ -130,28 +220,38 @@ async fn process_signature_range_with_limit(
     limit: Option<u8>,
 ) {
     let limit = limit.unwrap_or(GET_SIGNATURES_BY_ADDRESS_LIMIT);
+    let address = match &range.contract_address {
+        Some(address) => address.clone(),
+        None => read_state(|s| s.solana_contract_address.clone()),
+    };
     let mut before_signature = range.before_sol_sig.to_string();
-    let until_signature = range.until_sol_sig.to_string();
+    let until_signature = range.until_sol_sig.clone();
 
-    let mut result: Vec<String> = Vec::new();
+    let mut result: Vec<SolanaSignature> = Vec::new();
     let mut at_least_one_successful_call = false; // Flag to track if at least one call was successful
 
     loop {
         ic_canister_log::log!(
             DEBUG,
-            "\nScanning range:\n\tbefore: {before_signature}\n\tuntil: {until_signature}\n\tlimit: {limit}",
+            "\nScanning range:\n\tbefore: {before_signature}\n\tuntil: {}\n\tlimit: {limit}",
+            range.until_as_str(),
         );
 
         // get signatures for chunk
         match rpc_client
-            .get_signatures_for_address(limit, Some(&before_signature), &until_signature)
+            .get_signatures_for_address(
+                &address,
+                limit,
+                Some(&before_signature),
+                until_signature.as_ref(),
+            )
             .await
         {
             Ok(signatures) => {
                 // If at least one call was successful, add the initial element.
                 // Call is non inclusive, so we need to add the first element only once.
                 if !at_least_one_successful_call {
-                    result.push(before_signature.to_string());
+                    result.push(SolanaSignature::new(before_signature.to_string(), None));
                     at_least_one_successful_call = true;
                 }
 
@@ -165,7 +265,19 @@ async fn process_signature_range_with_limit(
                 // store the last signature to use it as before for the next chunk
                 let last_signature = signatures.last().unwrap();
                 before_signature = last_signature.signature.to_string();
-                result.extend(signatures.iter().map(|s| s.signature.to_string()));
+                for s in &signatures {
+                    match SolanaSignature::try_new(&s.signature, Some(s.slot)) {
+                        Ok(signature) => result.push(
+                            signature
+                                .with_block_time(s.block_time)
+                                .with_memo(s.memo.clone()),
+                        ),
+                        Err(err) => ic_canister_log::log!(INFO, "\nIgnoring signature: {err}"),
+                    }
+                }
+                mutate_state(|s| {
+                    s.record_range_cursor(&range, &before_signature, result.len() as u64)
+                });
             }
             Err(error) => {
                 // if RPC call failed to get signatures, retry later
@@ -183,10 +295,25 @@ async fn process_signature_range_with_limit(
 
     // Only process the signatures if at least one successful call was made
     if at_least_one_successful_call {
-        result
+        skip_known_signatures(result)
             .iter()
-            .for_each(|s| process_solana_signature(&SolanaSignature::new(s.to_string()), None));
+            .for_each(|signature| process_solana_signature(signature, None));
+    }
+}
+
+// Overlapping ranges return the same signatures, recording them again would count as a retry.
+fn skip_known_signatures(signatures: Vec<SolanaSignature>) -> Vec<SolanaSignature> {
+    let (known, new): (Vec<_>, Vec<_>) = signatures
+        .into_iter()
+        .partition(|signature| read_state(|s| s.is_known_signature(&signature.sol_sig)));
+    if !known.is_empty() {
+        ic_canister_log::log!(
+            DEBUG,
+            "\nSkipping {} already handled signatures",
+            known.len()
+        );
     }
+    new
 }
 
 pub async fn scrap_signatures() {
@@ -195,17 +322,34 @@ pub async fn scrap_signatures() {
         Err(_) => return,
     };
 
+    if is_rate_limited() {
+        return;
+    }
+
     let rpc_client = read_state(SolRpcClient::from_state);
     // filter out all events that have reached the retry limit
-    let filtered_signatures =
-        HashMapUtils::filter(&read_state(|s| s.solana_signatures.clone()), |s| {
+    let (filtered_signatures, exhausted_signatures) =
+        MapUtils::partition(&read_state(|s| s.solana_signatures.clone()), |s| {
             !s.retry.is_retry_limit_reached(SOLANA_SIGNATURE_RETRY_LIMIT)
         });
+    if !exhausted_signatures.is_empty() {
+        ic_canister_log::log!(
+            INFO,
+            "\nSignatures that reached the retry limit:\n{}",
+            MapUtils::format_keys_as_string(&exhausted_signatures)
+        );
+    }
+    // bound the work per tick, the rest is left for the next tick
+    let filtered_signatures = MapUtils::limit_by(
+        filtered_signatures,
+        read_state(|s| s.max_events_per_tick) as usize,
+        |s| s.retry.get_retries(),
+    );
 
     ic_canister_log::log!(
         DEBUG,
         "\nProcessing signatures:\n{}",
-        HashMapUtils::format_keys_as_string(&filtered_signatures)
+        MapUtils::format_keys_as_string(&filtered_signatures)
     );
 
     let transactions = process_signatures_with_limit(&rpc_client, &filtered_signatures, None).await;
@@ -219,6 +363,31 @@ pub async fn scrap_signatures() {
     parse_log_messages(&transactions);
 }
 
+// The batch call failed, not the individual signatures: they stay queued for the next tick
+// without consuming their retries. A call for a single signature did fail for that signature,
+// it consumes a retry so a persistently failing signature is eventually abandoned.
+fn record_batch_call_failure(
+    chunk: &[&SolanaSignature],
+    err: SolRpcError,
+    process: impl FnMut(&mut State, EventType),
+) {
+    if let [signature] = chunk {
+        record_solana_signature(signature, Some(DepositError::RpcCallFailed(err)), process);
+        return;
+    }
+    let key = format!(
+        "{}-{}",
+        chunk.first().unwrap().sol_sig,
+        chunk.last().unwrap().sol_sig
+    );
+    ic_canister_log::log!(
+        DEBUG,
+        "\nFailed to get transactions of {} signatures: {err}",
+        chunk.len()
+    );
+    mutate_state(|s| s.record_deposit_error(key, DepositError::RpcCallFailed(err)));
+}
+
 async fn process_signatures_with_limit(
     rpc_client: &SolRpcClient,
     signatures_map: &HashMap<String, SolanaSignature>,
@@ -227,7 +396,9 @@ async fn process_signatures_with_limit(
     let limit = limit.unwrap_or(GET_TRANSACTIONS_LIMIT);
     let mut transactions: Vec<(SolanaSignature, GetTransactionResponse)> = Vec::new();
 
-    let signatures: Vec<&SolanaSignature> = signatures_map.values().collect();
+    // process signatures in ascending slot order, signatures with unknown slot go last
+    let mut signatures: Vec<&SolanaSignature> = signatures_map.values().collect();
+    signatures.sort_by_key(|s| (s.slot.is_none(), s.slot));
     for chunk in signatures.chunks(limit as usize) {
         let signatures = chunk.iter().map(|elem| &elem.sol_sig).collect();
 
@@ -237,6 +408,13 @@ async fn process_signatures_with_limit(
                     let signature = signatures_map.get(&key).unwrap().clone();
 
                     match value {
+                        // retrying a permanently failing signature is pointless
+                        Err(err) if err.is_permanent() => {
+                            process_invalid_event(
+                                &signature,
+                                DepositError::SignatureFailed { sig: key, err },
+                            );
+                        }
                         Err(err) => {
                             process_solana_signature(
                                 &signature,
@@ -255,21 +433,35 @@ async fn process_signatures_with_limit(
                     }
                 }
             }
-            Err(err) => {
-                // if RPC call failed to get transactions, skip the transactions and retry later
-                chunk.iter().for_each(|s| {
-                    process_solana_signature(*s, Some(DepositError::RpcCallFailed(err.clone())))
-                });
-            }
+            Err(err) => record_batch_call_failure(chunk, err, process_event),
         };
     }
 
+    sort_by_chain_order(&mut transactions);
+
     return transactions;
 }
 
+// The RPC may return transactions of a batch in any order, deposit ids are assigned
+// in this order, so it has to follow the chain deterministically.
+fn sort_by_chain_order(transactions: &mut [(SolanaSignature, GetTransactionResponse)]) {
+    transactions.sort_by(|(_, a), (_, b)| a.chain_order().cmp(&b.chain_order()));
+}
+
+// RPC calling tasks back off while the provider is rate-limiting us.
+fn is_rate_limited() -> bool {
+    let rate_limited = read_state(|s| s.is_rate_limited(ic_cdk::api::time()));
+    if rate_limited {
+        ic_canister_log::log!(DEBUG, "\nRPC provider rate limit backoff, skipping task");
+    }
+    rate_limited
+}
+
 fn parse_log_messages(transactions: &Vec<(SolanaSignature, GetTransactionResponse)>) {
     for (signature, transaction) in transactions {
-        match process_transaction_logs(transaction) {
+        match process_transaction_logs(transaction)
+            .and_then(|deposit| check_memo_recipient(signature, deposit))
+        {
             Ok(deposit) => {
                 process_accepted_event(&deposit, None);
             }
@@ -280,24 +472,72 @@ fn parse_log_messages(transactions: &Vec<(SolanaSignature, GetTransactionRespons
     }
 }
 
+// A memo naming a principal is a cross-check of the recipient decoded from the deposit data,
+// a malformed payload must not mint to someone else.
+fn check_memo_recipient(
+    signature: &SolanaSignature,
+    deposit: DepositEvent,
+) -> Result<DepositEvent, DepositError> {
+    match signature
+        .memo_principals()
+        .into_iter()
+        .find(|principal| *principal != deposit.to_icp_address)
+    {
+        Some(memo) => Err(DepositError::DepositEventFailed {
+            sig: signature.sol_sig.to_string(),
+            err: DepositEventError::MemoRecipientMismatch {
+                memo,
+                data: deposit.to_icp_address,
+            },
+        }),
+        None => Ok(deposit),
+    }
+}
+
+// Whether the logs are those of a successful deposit to one of the watched contracts.
+fn is_deposit_log(msgs: &[String], program_data_msg: &str) -> bool {
+    let deposit_msg = "Program log: Instruction: Deposit";
+    let success_msgs: Vec<String> = read_state(|s| {
+        s.solana_contract_addresses()
+            .map(|address| format!("Program {address} success"))
+            .collect()
+    });
+
+    msgs.contains(&String::from(deposit_msg))
+        && success_msgs
+            .iter()
+            .any(|success_msg| msgs.contains(success_msg))
+        && msgs.iter().any(|s| s.starts_with(program_data_msg))
+}
+
 fn process_transaction_logs(
     transaction: &GetTransactionResponse,
 ) -> Result<DepositEvent, DepositError> {
-    let deposit_msg = "Program log: Instruction: Deposit";
-    let success_msg = &format!(
-        "Program {} success",
-        read_state(|s| s.solana_contract_address.clone())
-    );
     let program_data_msg = "Program data: ";
 
     let signature = &transaction.transaction.signatures[0];
     let solana_address = &transaction.transaction.message.account_keys[0];
     let msgs = &transaction.meta.log_messages;
 
-    if msgs.contains(&String::from(deposit_msg))
-        && msgs.contains(&String::from(success_msg))
-        && msgs.iter().any(|s| s.starts_with(program_data_msg))
-    {
+    // A failed transaction still carries the logs of the instructions executed before the failure,
+    // so its deposit logs must never be trusted.
+    if let Some(err) = &transaction.meta.err {
+        return Err(DepositError::TransactionFailed {
+            sig: signature.to_string(),
+            err: err.to_string(),
+        });
+    }
+
+    // Minted events of pruned slots are gone, so a deposit from there can no longer be told apart
+    // from an already minted one.
+    if read_state(|s| s.is_pruned_slot(transaction.slot)) {
+        return Err(DepositError::PrunedSlot {
+            sig: signature.to_string(),
+            slot: transaction.slot,
+        });
+    }
+
+    if is_deposit_log(msgs, program_data_msg) {
         if let Some(program_data) = msgs.iter().find(|s| s.starts_with(program_data_msg)) {
             let base64_data = program_data.trim_start_matches(program_data_msg);
             let deposit: Result<DepositEvent, DepositEventError> = DepositEvent::new(
@@ -305,11 +545,12 @@ fn process_transaction_logs(
                 signature.as_str(),
                 solana_address.as_str(),
                 base64_data,
+                transaction.slot,
             );
 
             match deposit {
                 Ok(deposit) => {
-                    return Ok(deposit);
+                    return check_max_deposit_amount(deposit);
                 }
                 Err(err) => {
                     return Err(DepositError::DepositEventFailed {
@@ -326,68 +567,192 @@ fn process_transaction_logs(
     }
 }
 
-pub async fn mint_gsol() {
-    use icrc_ledger_client_cdk::{CdkRuntime, ICRC1Client};
-    use icrc_ledger_types::icrc1::{account::Account, transfer::TransferArg};
+// A buggy contract could emit any amount, this caps what a single deposit mints.
+fn check_max_deposit_amount(deposit: DepositEvent) -> Result<DepositEvent, DepositError> {
+    match read_state(|s| s.max_deposit_amount.clone()) {
+        Some(maximum) if deposit.amount.0 > maximum => Err(DepositError::AmountAboveMaximum {
+            sig: deposit.sol_sig,
+            amount: deposit.amount,
+            max: Nat::from(maximum),
+        }),
+        _ => Ok(deposit),
+    }
+}
 
+pub async fn mint_gsol() {
     let _guard = match TimerGuard::new(TaskType::MintGSol) {
         Ok(guard) => guard,
         Err(_) => return,
     };
 
+    if read_state(|s| s.is_minting_paused(ic_cdk::api::time())) {
+        ic_canister_log::log!(DEBUG, "\nMinting is paused, skipping task");
+        return;
+    }
+
     let ledger_canister_id = read_state(|s| s.ledger_id);
     // filter out all events that have reached the retry limit
-    let filtered_events = HashMapUtils::filter(&read_state(|s| s.accepted_events.clone()), |e| {
-        !e.retry.is_retry_limit_reached(MINT_GSOL_RETRY_LIMIT)
+    let (filtered_events, exhausted_events) =
+        MapUtils::partition(&read_state(|s| s.accepted_events.clone()), |e| {
+            !e.retry.is_retry_limit_reached(MINT_GSOL_RETRY_LIMIT)
+        });
+    // deposits too close to the tip stay accepted and are picked up by a later tick
+    let filtered_events = MapUtils::filter(&filtered_events, |e| {
+        read_state(|s| s.is_deposit_confirmed(e))
     });
+    if !exhausted_events.is_empty() {
+        ic_canister_log::log!(
+            INFO,
+            "\nMoving deposits that reached the minting retry limit to dead letters:\n{}",
+            MapUtils::format_keys_as_string(&exhausted_events)
+        );
+        mutate_state(|s| {
+            for event in exhausted_events.into_values() {
+                process_event(
+                    s,
+                    EventType::DeadLetterEvent {
+                        event_source: event,
+                    },
+                );
+            }
+        });
+    }
+    // bound the work per tick, the rest is left for the next tick
+    let filtered_events = MapUtils::limit_by(
+        filtered_events,
+        read_state(|s| s.max_events_per_tick) as usize,
+        |e| e.retry.get_retries(),
+    );
 
     ic_canister_log::log!(
         DEBUG,
         "\nMinting gSOL:\n{}",
-        HashMapUtils::format_keys_as_string(&filtered_events)
+        MapUtils::format_keys_as_string(&filtered_events)
     );
 
+    for event in sorted_for_minting(filtered_events) {
+        let result = mint_deposit(event).await;
+
+        // a rejected transfer still reached the ledger, only failed calls count towards the breaker
+        let reached_ledger = !matches!(
+            result,
+            Err(DepositError::SendingMessageToLedgerFailed { .. })
+        );
+        if mutate_state(|s| s.record_ledger_call(reached_ledger, ic_cdk::api::time())) {
+            ic_canister_log::log!(
+                INFO,
+                "\nALERT: minting paused after {MINTING_CIRCUIT_BREAKER_THRESHOLD} consecutive failed calls to ledger {ledger_canister_id}"
+            );
+            break;
+        }
+    }
+
+    prune_events();
+}
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
+pub struct ForceMintResult {
+    pub deposit_id: u64,
+    pub sol_sig: String,
+    pub block_index: Option<u64>,
+    pub error: Option<DepositError>,
+}
+
+// Mints up to `limit` confirmed accepted deposits regardless of the circuit breaker.
+// Shares the lock of the mint_gsol timer task, so a deposit is never minted twice concurrently.
+pub async fn force_mint(limit: usize) -> Vec<ForceMintResult> {
+    let _guard = TimerGuard::new(TaskType::MintGSol)
+        .unwrap_or_else(|_| ic_cdk::trap("minting is already in progress, retry later"));
+
+    let events = read_state(|s| {
+        s.accepted_events
+            .iter()
+            .filter(|(_, e)| s.is_deposit_confirmed(e))
+            .map(|(k, e)| (k.clone(), e.clone()))
+            .collect::<HashMap<_, _>>()
+    });
+
+    let mut results = Vec::new();
+    for event in sorted_for_minting(events).into_iter().take(limit) {
+        let (deposit_id, sol_sig) = (event.id, event.sol_sig.clone());
+        let result = mint_deposit(event).await;
+        results.push(ForceMintResult {
+            deposit_id,
+            sol_sig,
+            block_index: result.as_ref().ok().copied(),
+            error: result.err(),
+        });
+    }
+    results
+}
+
+// mint in ascending slot order, deposits with unknown slot go last
+fn sorted_for_minting(events: HashMap<String, DepositEvent>) -> Vec<DepositEvent> {
+    let mut events: Vec<DepositEvent> = events.into_values().collect();
+    events.sort_by_key(|e| (e.slot.is_none(), e.slot, e.id));
+    events
+}
+
+// Transfers the deposit to its recipient and records the outcome, returns the mint block index.
+async fn mint_deposit(mut event: DepositEvent) -> Result<u64, DepositError> {
+    use icrc_ledger_client_cdk::{CdkRuntime, ICRC1Client};
+    use icrc_ledger_types::icrc1::{account::Account, transfer::TransferArg};
+
+    let ledger_canister_id = read_state(|s| s.ledger_id);
     let client = ICRC1Client {
         runtime: CdkRuntime,
         ledger_canister_id,
     };
 
-    for (_, mut event) in filtered_events {
-        match client
-            .transfer(TransferArg {
-                from_subaccount: None,
-                to: Account {
-                    owner: event.to_icp_address,
-                    subaccount: None,
-                },
-                amount: event.amount.clone(),
-                fee: None,
-                created_at_time: Some(ic_cdk::api::time()),
-                // Memo is limited to 32 bytes in size, so can't fit much in there
-                memo: Some(LedgerMemo(event.id).into()),
-            })
-            .await
-        {
-            Ok(Ok(block_index)) => {
-                let block_index = block_index.0.to_u64().expect("nat does not fit into u64");
-                event.update_mint_block_index(block_index);
-                process_minted_event(&event);
-            }
-            Ok(Err(err)) => {
-                process_accepted_event(&event, Some(DepositError::MintingGSolFailed(err.clone())));
-            }
-            Err(err) => {
-                process_accepted_event(
-                    &event,
-                    Some(DepositError::SendingMessageToLedgerFailed {
-                        id: ledger_canister_id.to_string(),
-                        code: err.0,
-                        msg: err.1,
-                    }),
-                );
-            }
-        };
+    let result = client
+        .transfer(TransferArg {
+            from_subaccount: None,
+            to: Account {
+                owner: event.to_icp_address,
+                subaccount: None,
+            },
+            // deposits are denominated in lamports, the ledger may use different decimals
+            amount: read_state(|s| s.lamports_to_gsol(&event.amount)),
+            fee: None,
+            created_at_time: Some(ic_cdk::api::time()),
+            // Memo is limited to 32 bytes in size, so can't fit much in there
+            memo: Some(LedgerMemo::deposit(event.id).into()),
+        })
+        .await;
+
+    let error = match result {
+        Ok(Ok(block_index)) => {
+            let block_index = block_index.0.to_u64().expect("nat does not fit into u64");
+            event.update_mint_block_index(block_index);
+            process_minted_event(&event);
+            return Ok(block_index);
+        }
+        Ok(Err(err)) => DepositError::MintingGSolFailed(err),
+        Err(err) => DepositError::SendingMessageToLedgerFailed {
+            id: ledger_canister_id.to_string(),
+            code: err.0,
+            msg: err.1,
+        },
+    };
+
+    process_accepted_event(&event, Some(error.clone()));
+    Err(error)
+}
+
+// Prunes minted and invalid events older than the configured retention.
+fn prune_events() {
+    let before_slot = match read_state(|s| s.retention_slots.zip(s.last_observed_slot)) {
+        Some((retention_slots, latest_slot)) => latest_slot.saturating_sub(retention_slots),
+        None => return,
+    };
+
+    if !read_state(|s| s.has_prunable_events(before_slot)) {
+        return;
     }
+
+    ic_canister_log::log!(INFO, "\nPruning events before slot {before_slot}");
+
+    mutate_state(|s| process_event(s, EventType::PrunedEvents { before_slot }));
 }
 
 /// Process events
@@ -423,6 +788,9 @@ fn process_accepted_event(event: &DepositEvent, err: Option<DepositError>) {
     }
 
     mutate_state(|s| {
+        if let Some(err) = err.clone() {
+            s.record_deposit_error(event.sol_sig.to_string(), err);
+        }
         process_event(
             s,
             EventType::AcceptedEvent {
@@ -437,6 +805,7 @@ fn process_invalid_event(signature: &SolanaSignature, err: DepositError) {
     ic_canister_log::log!(DEBUG, "\nSignature {} : {err}", signature.sol_sig);
 
     mutate_state(|s| {
+        s.record_deposit_error(signature.sol_sig.to_string(), err.clone());
         process_event(
             s,
             EventType::InvalidEvent {
@@ -448,6 +817,14 @@ fn process_invalid_event(signature: &SolanaSignature, err: DepositError) {
 }
 
 fn process_solana_signature(signature: &SolanaSignature, err: Option<DepositError>) {
+    record_solana_signature(signature, err, process_event);
+}
+
+fn record_solana_signature(
+    signature: &SolanaSignature,
+    err: Option<DepositError>,
+    process: impl FnMut(&mut State, EventType),
+) {
     if let Some(err) = err.clone() {
         ic_canister_log::log!(DEBUG, "{err}");
     } else {
@@ -459,55 +836,119 @@ fn process_solana_signature(signature: &SolanaSignature, err: Option<DepositErro
     }
 
     mutate_state(|s| {
-        process_event(
+        if let Some(err) = err.clone() {
+            s.record_deposit_error(signature.sol_sig.to_string(), err);
+        }
+        process_retry_event(
             s,
             EventType::SolanaSignature {
                 signature: signature.clone(),
                 fail_reason: err.map(|e| e.to_string()),
             },
+            |s| s.is_signature_retry_limit_reached(&signature.sol_sig),
+            (RetryKind::Signature, signature.sol_sig.to_string()),
+            process,
         );
     });
 }
 
-fn process_new_solana_signature_range(newest_signature: &str, until_signature: &str) {
+// Processes the retry and, the first time it makes the item cross its retry limit,
+// logs and records that the item is abandoned.
+fn process_retry_event(
+    state: &mut State,
+    retry: EventType,
+    is_retry_limit_reached: impl Fn(&State) -> bool,
+    (kind, key): (RetryKind, String),
+    mut process: impl FnMut(&mut State, EventType),
+) {
+    let exhausted = is_retry_limit_reached(state);
+    process(state, retry);
+    if !exhausted && is_retry_limit_reached(state) {
+        ic_canister_log::log!(
+            INFO,
+            "\nALERT: {kind:?} {key} reached its retry limit and is abandoned"
+        );
+        process(state, EventType::RetryLimitReached { kind, key });
+    }
+}
+
+// `contract_address` is None for the primary contract, without `until_signature` only the
+// last known signature is recorded.
+fn process_new_solana_signature_range(
+    newest_signature: &str,
+    until_signature: Option<&String>,
+    contract_address: Option<&String>,
+) {
     ic_canister_log::log!(DEBUG, "\nNew signature found: {newest_signature}",);
 
     mutate_state(|s| {
-        process_event(
-            s,
-            EventType::LastKnownSolanaSignature(newest_signature.to_string()),
-        );
-        process_event(
-            s,
-            EventType::NewSolanaSignatureRange(SolanaSignatureRange::new(
+        let last_known = match contract_address {
+            None => EventType::LastKnownSolanaSignature(newest_signature.to_string()),
+            Some(address) => EventType::LastKnownContractSignature {
+                address: address.to_string(),
+                signature: newest_signature.to_string(),
+            },
+        };
+        process_event(s, last_known);
+
+        if let Some(until_signature) = until_signature {
+            let range = SolanaSignatureRange::new(
                 newest_signature.to_string(),
-                until_signature.to_string(),
-            )),
-        );
+                Some(until_signature.to_string()),
+            )
+            .with_contract_address(contract_address.cloned());
+
+            record_new_signature_range(s, range, process_event);
+        }
     });
 }
 
+// Ranges of consecutive ticks are contiguous, a backlog is collapsed into one range
+// (before is scanned inclusively and until exclusively, so nothing is skipped).
+fn record_new_signature_range(
+    state: &mut State,
+    mut range: SolanaSignatureRange,
+    mut process: impl FnMut(&mut State, EventType),
+) {
+    if let Some(next) = state.mergeable_signature_range(&range) {
+        process(state, EventType::RemoveSolanaSignatureRange(next.clone()));
+        range.until_sol_sig = next.until_sol_sig;
+    }
+
+    process(state, EventType::NewSolanaSignatureRange(range));
+}
+
 fn process_retry_solana_signature_range(
     range: &SolanaSignatureRange,
     before_signature: &str,
-    until_signature: &str,
+    until_signature: &Option<String>,
     error: DepositError,
 ) {
+    let failed_sub_range =
+        SolanaSignatureRange::new(before_signature.to_string(), until_signature.clone())
+            .with_contract_address(range.contract_address.clone());
+    // without progress the range itself failed, its retries are counted instead of being
+    // reset by re-recording the same bounds
+    let new_range =
+        (failed_sub_range.before_sol_sig != range.before_sol_sig).then(|| failed_sub_range.clone());
+    let until_signature = failed_sub_range.until_as_str();
     let error_msg = format!("\nFailed to get signatures for address:\n\tbefore: {before_signature}\n\tuntil: {until_signature}\n\terror: {error:?}");
     ic_canister_log::log!(DEBUG, "{error_msg}");
 
     mutate_state(|s| {
-        process_event(
+        s.record_deposit_error(format!("{before_signature}-{until_signature}"), error);
+        let key = format!("{}-{}", range.before_sol_sig, range.until_as_str());
+        process_retry_event(
             s,
             EventType::RetrySolanaSignatureRange {
                 range: range.clone(),
-                failed_sub_range: Some(SolanaSignatureRange::new(
-                    before_signature.to_string(),
-                    until_signature.to_string(),
-                )),
+                failed_sub_range: new_range,
                 fail_reason: error_msg,
             },
-        )
+            |s| s.is_range_retry_limit_reached(range),
+            (RetryKind::SignatureRange, key),
+            process_event,
+        );
     });
 }
 
@@ -516,10 +957,429 @@ fn remove_solana_signature_range(range: &SolanaSignatureRange) {
         DEBUG,
         "\nRange completed:\n\tbefore: {}\n\tuntil: {}",
         range.before_sol_sig,
-        range.until_sol_sig,
+        range.until_as_str(),
     );
 
     mutate_state(|s| {
         process_event(s, EventType::RemoveSolanaSignatureRange(range.clone()));
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{self, SOLANA_ADDRESS, SOLANA_CONTRACT_ADDRESS};
+    use serde_json::{json, Value};
+
+    const SIGNATURE: &str =
+        "4ZxHBTGAKbRR2h6d5qqamGRndpnpbFk9PSBCq5WC2B9pGHeHjTxuaBjyqRyaKQExSSHRbHfdpRZrTgiwxX8Y4Qw5";
+
+    // A getTransaction result of a deposit to the contract, `err` is the `meta.err` of the transaction.
+    fn deposit_transaction(err: Value) -> GetTransactionResponse {
+        serde_json::from_value(json!({
+            "blockTime": 1_711_616_761,
+            "meta": {
+                "computeUnitsConsumed": 9_417,
+                "err": err,
+                "fee": 5_000,
+                "innerInstructions": [],
+                "loadedAddresses": { "readonly": [], "writable": [] },
+                "logMessages": [
+                    format!("Program {SOLANA_CONTRACT_ADDRESS} invoke [1]"),
+                    "Program log: Instruction: Deposit",
+                    "Program data: AAAAAA==",
+                    format!("Program {SOLANA_CONTRACT_ADDRESS} success"),
+                ],
+                "postBalances": [],
+                "postTokenBalances": [],
+                "preBalances": [],
+                "preTokenBalances": [],
+                "rewards": [],
+                "status": { "Ok": null }
+            },
+            "slot": 286_567_890,
+            "transaction": {
+                "message": {
+                    "accountKeys": [SOLANA_ADDRESS, SOLANA_CONTRACT_ADDRESS],
+                    "header": {
+                        "numReadonlySignedAccounts": 0,
+                        "numReadonlyUnsignedAccounts": 1,
+                        "numRequiredSignatures": 1
+                    },
+                    "instructions": [],
+                    "recentBlockhash": "EkSnNWid2cvwEVnVx9aBqawnmiCNiDgp3gUdkDPTKN1N"
+                },
+                "signatures": [SIGNATURE]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn should_not_accept_deposit_of_failed_transaction() {
+        test_fixtures::set_state(test_fixtures::state());
+        let transaction = deposit_transaction(json!({ "InstructionError": [0, "Custom"] }));
+
+        assert_eq!(
+            process_transaction_logs(&transaction),
+            Err(DepositError::TransactionFailed {
+                sig: SIGNATURE.to_string(),
+                err: r#"{"InstructionError":[0,"Custom"]}"#.to_string(),
+            })
+        );
+        // no deposit id was consumed for it
+        assert_eq!(read_state(|s| s.deposit_id_counter), 0);
+    }
+
+    #[test]
+    fn should_take_newest_signature_of_multi_signature_response() {
+        use crate::sol_rpc_client::responses::JsonRpcResponse;
+
+        let newest = SIGNATURE;
+        let older = test_fixtures::SOLANA_INITIAL_SIGNATURE;
+        let response = format!(
+            r#"{{"jsonrpc":"2.0","result":[
+                {{"blockTime":1711616790,"confirmationStatus":"finalized","err":null,"memo":null,"signature":"{newest}","slot":300}},
+                {{"blockTime":1711616780,"confirmationStatus":"finalized","err":null,"memo":null,"signature":"{older}","slot":200}},
+                {{"blockTime":null,"confirmationStatus":"confirmed","err":null,"memo":null,"signature":"{older}","slot":100}}
+            ],"id":1}}"#
+        );
+        let response: JsonRpcResponse<Vec<SignatureResponse>> =
+            serde_json::from_str(&response).unwrap();
+
+        assert_eq!(
+            newest_signature(&response.result.unwrap()),
+            Some(newest.to_string())
+        );
+        assert_eq!(newest_signature(&[]), None);
+    }
+
+    #[test]
+    fn should_mint_in_ascending_slot_order() {
+        let events: HashMap<String, DepositEvent> = [
+            (0, Some(300)),
+            (1, None),
+            (2, Some(100)),
+            (3, Some(200)),
+            (4, Some(100)),
+        ]
+        .into_iter()
+        .map(|(id, slot)| {
+            let sig = format!("sig{id}");
+            let event = DepositEvent::new_for_test(id, &sig, test_fixtures::principal(1), 1, slot);
+            (sig, event)
+        })
+        .collect();
+
+        let order: Vec<u64> = sorted_for_minting(events).iter().map(|e| e.id).collect();
+
+        // equal slots are minted by deposit id, deposits of unknown slot last
+        assert_eq!(order, vec![2, 4, 3, 0, 1]);
+    }
+
+    #[test]
+    fn should_skip_signatures_found_by_an_overlapping_range() {
+        let mut state = test_fixtures::state();
+        // the older range queued sig1, and sig2 was already accepted
+        for (sig, slot) in [("sig1", 10), ("sig2", 11)] {
+            state.record_or_retry_solana_signature(SolanaSignature::new(
+                sig.to_string(),
+                Some(slot),
+            ));
+        }
+        crate::state::audit::apply_state_transition(
+            &mut state,
+            &EventType::AcceptedEvent {
+                event_source: DepositEvent::new_for_test(
+                    0,
+                    "sig2",
+                    test_fixtures::principal(1),
+                    1_000,
+                    Some(11),
+                ),
+                fail_reason: None,
+            },
+        );
+        test_fixtures::set_state(state);
+
+        let newer_range = ["sig3", "sig2", "sig1"]
+            .into_iter()
+            .map(|sig| SolanaSignature::new(sig.to_string(), Some(12)))
+            .collect();
+        let new: Vec<String> = skip_known_signatures(newer_range)
+            .into_iter()
+            .map(|s| s.sol_sig)
+            .collect();
+
+        assert_eq!(new, vec!["sig3"]);
+        assert_eq!(
+            read_state(|s| s.solana_signatures["sig1"].retry.get_retries()),
+            0
+        );
+    }
+
+    #[test]
+    fn should_not_consume_signature_retries_when_the_batch_call_fails() {
+        let mut state = test_fixtures::state();
+        for (sig, slot) in [("sig1", 10), ("sig2", 11)] {
+            state.record_or_retry_solana_signature(SolanaSignature::new(
+                sig.to_string(),
+                Some(slot),
+            ));
+        }
+        test_fixtures::set_state(state);
+
+        let chunk: Vec<SolanaSignature> = read_state(|s| {
+            ["sig1", "sig2"]
+                .iter()
+                .map(|sig| s.solana_signatures[*sig].clone())
+                .collect()
+        });
+        let chunk: Vec<&SolanaSignature> = chunk.iter().collect();
+        record_batch_call_failure(&chunk, SolRpcError::RateLimited, |_, event| {
+            panic!("unexpected event {event:?}")
+        });
+
+        read_state(|s| {
+            assert_eq!(s.solana_signatures.len(), 2);
+            assert!(s
+                .solana_signatures
+                .values()
+                .all(|signature| signature.retry.get_retries() == 0));
+            assert_eq!(
+                s.deposit_errors.back(),
+                Some(&(
+                    "sig1-sig2".to_string(),
+                    DepositError::RpcCallFailed(SolRpcError::RateLimited)
+                ))
+            );
+        });
+    }
+
+    #[test]
+    fn should_order_transactions_deterministically() {
+        let transaction = |sig: &str, slot: u64, block_time: u64| {
+            let mut tx = deposit_transaction(Value::Null);
+            tx.slot = slot;
+            tx.block_time = block_time;
+            tx.transaction.signatures = vec![sig.to_string()];
+            (SolanaSignature::new(sig.to_string(), Some(slot)), tx)
+        };
+        let transactions = vec![
+            transaction("d", 20, 1),
+            transaction("c", 10, 2),
+            transaction("b", 10, 1),
+            transaction("a", 10, 1),
+        ];
+
+        // every order of the batch yields the same order, so the same deposit ids
+        let mut reversed: Vec<_> = transactions.iter().rev().cloned().collect();
+        let mut forward = transactions;
+        sort_by_chain_order(&mut forward);
+        sort_by_chain_order(&mut reversed);
+
+        let order = |txs: &[(SolanaSignature, GetTransactionResponse)]| -> Vec<String> {
+            txs.iter().map(|(s, _)| s.sol_sig.clone()).collect()
+        };
+        assert_eq!(order(&forward), vec!["a", "b", "c", "d"]);
+        assert_eq!(order(&reversed), order(&forward));
+    }
+
+    #[test]
+    fn should_accept_deposits_of_every_configured_contract() {
+        const V2_CONTRACT_ADDRESS: &str = "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS";
+
+        let mut arg = test_fixtures::init_arg();
+        arg.additional_solana_contract_addresses = Some(vec![V2_CONTRACT_ADDRESS.to_string()]);
+        test_fixtures::set_state(State::try_from(arg).unwrap());
+        assert_eq!(
+            read_state(|s| s.solana_contract_addresses().cloned().collect::<Vec<_>>()),
+            vec![SOLANA_CONTRACT_ADDRESS, V2_CONTRACT_ADDRESS]
+        );
+
+        let logs = |contract: &str| -> Vec<String> {
+            vec![
+                format!("Program {contract} invoke [1]"),
+                "Program log: Instruction: Deposit".to_string(),
+                "Program data: AAAAAA==".to_string(),
+                format!("Program {contract} success"),
+            ]
+        };
+        assert!(is_deposit_log(
+            &logs(SOLANA_CONTRACT_ADDRESS),
+            "Program data: "
+        ));
+        assert!(is_deposit_log(&logs(V2_CONTRACT_ADDRESS), "Program data: "));
+        assert!(!is_deposit_log(&logs(SOLANA_ADDRESS), "Program data: "));
+    }
+
+    #[test]
+    fn should_check_the_recipient_named_by_the_memo() {
+        let recipient = test_fixtures::principal(1);
+        let with_memo = |memo: Option<String>| {
+            SolanaSignature::new(SIGNATURE.to_string(), Some(10)).with_memo(memo)
+        };
+        let deposit = DepositEvent::new_for_test(0, SIGNATURE, recipient, 1_000, Some(10));
+
+        let matching = format!("[63] {}", recipient.to_text());
+        for memo in [None, Some(matching), Some("[5] hello".to_string())] {
+            assert_eq!(
+                check_memo_recipient(&with_memo(memo), deposit.clone()),
+                Ok(deposit.clone())
+            );
+        }
+
+        let other = test_fixtures::principal(2);
+        let mismatching = format!("[5] hello; [63] {}", other.to_text());
+        assert_eq!(
+            check_memo_recipient(&with_memo(Some(mismatching)), deposit),
+            Err(DepositError::DepositEventFailed {
+                sig: SIGNATURE.to_string(),
+                err: DepositEventError::MemoRecipientMismatch {
+                    memo: other,
+                    data: recipient,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn should_record_retry_limit_reached_exactly_once() {
+        use crate::constants::SOLANA_SIGNATURE_RETRY_LIMIT;
+        use crate::state::audit::apply_state_transition;
+
+        let mut state = test_fixtures::state();
+        let signature = SolanaSignature::new("sig1".to_string(), Some(10));
+        state.record_or_retry_solana_signature(signature.clone());
+
+        // `process_event` also writes the event log, which needs the canister time
+        let mut recorded = vec![];
+        for _ in 0..SOLANA_SIGNATURE_RETRY_LIMIT as usize + 5 {
+            process_retry_event(
+                &mut state,
+                EventType::SolanaSignature {
+                    signature: signature.clone(),
+                    fail_reason: Some("timeout".to_string()),
+                },
+                |s| s.is_signature_retry_limit_reached("sig1"),
+                (RetryKind::Signature, "sig1".to_string()),
+                |s, event| {
+                    apply_state_transition(s, &event);
+                    recorded.push(event);
+                },
+            );
+        }
+
+        let abandoned: Vec<&EventType> = recorded
+            .iter()
+            .filter(|event| matches!(event, EventType::RetryLimitReached { .. }))
+            .collect();
+        assert_eq!(
+            abandoned,
+            vec![&EventType::RetryLimitReached {
+                kind: RetryKind::Signature,
+                key: "sig1".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn should_accept_deposits_up_to_the_maximum_amount() {
+        let mut state = test_fixtures::state();
+        state.max_deposit_amount = Some(1_000u64.into());
+        test_fixtures::set_state(state);
+
+        for amount in [1, 1_000] {
+            let deposit =
+                DepositEvent::new_for_test(0, SIGNATURE, test_fixtures::principal(1), amount, None);
+            assert_eq!(check_max_deposit_amount(deposit.clone()), Ok(deposit));
+        }
+    }
+
+    #[test]
+    fn should_reject_deposits_above_the_maximum_amount() {
+        let deposit =
+            DepositEvent::new_for_test(0, SIGNATURE, test_fixtures::principal(1), 1_001, None);
+        test_fixtures::set_state(test_fixtures::state());
+        assert_eq!(
+            check_max_deposit_amount(deposit.clone()),
+            Ok(deposit.clone())
+        );
+
+        let mut state = test_fixtures::state();
+        state.max_deposit_amount = Some(1_000u64.into());
+        test_fixtures::set_state(state);
+
+        let error = check_max_deposit_amount(deposit).unwrap_err();
+        assert_eq!(
+            error,
+            DepositError::AmountAboveMaximum {
+                sig: SIGNATURE.to_string(),
+                amount: Nat::from(1_001u64),
+                max: Nat::from(1_000u64),
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Signature {SIGNATURE} : amount 1_001 is above the maximum deposit amount 1_000"
+            )
+        );
+    }
+
+    #[test]
+    fn should_merge_contiguous_signature_ranges() {
+        use crate::state::audit::apply_state_transition;
+
+        let range = |before: &str, until: &str| {
+            SolanaSignatureRange::new(before.to_string(), Some(until.to_string()))
+        };
+        let apply = |s: &mut State, event: EventType| apply_state_transition(s, &event);
+        let mut state = test_fixtures::state();
+        record_new_signature_range(&mut state, range("sig2", "sig1"), apply);
+        record_new_signature_range(&mut state, range("sig3", "sig2"), apply);
+
+        let ranges: Vec<&SolanaSignatureRange> = state.solana_signature_ranges.values().collect();
+        assert_eq!(ranges, vec![&range("sig3", "sig1")]);
+
+        // a range with a gap in between is kept apart
+        record_new_signature_range(&mut state, range("sig5", "sig4"), apply);
+        assert_eq!(state.solana_signature_ranges.len(), 2);
+    }
+
+    #[test]
+    fn should_exhaust_signature_whose_single_signature_call_keeps_failing() {
+        use crate::constants::SOLANA_SIGNATURE_RETRY_LIMIT;
+        use crate::state::audit::apply_state_transition;
+
+        let signature = SolanaSignature::new("sig1".to_string(), Some(10));
+        let mut state = test_fixtures::state();
+        state.record_or_retry_solana_signature(signature.clone());
+        test_fixtures::set_state(state);
+
+        // `process_event` also writes the event log, which needs the canister time
+        let mut recorded = vec![];
+        for _ in 0..SOLANA_SIGNATURE_RETRY_LIMIT as usize + 5 {
+            record_batch_call_failure(
+                &[&signature],
+                SolRpcError::HttpStatus {
+                    code: 503,
+                    body_excerpt: "unavailable".to_string(),
+                },
+                |s, event| {
+                    apply_state_transition(s, &event);
+                    recorded.push(event);
+                },
+            );
+        }
+
+        assert!(read_state(|s| s.is_signature_retry_limit_reached("sig1")));
+        assert_eq!(
+            recorded
+                .iter()
+                .filter(|event| matches!(event, EventType::RetryLimitReached { .. }))
+                .count(),
+            1
+        );
+    }
+}
@@ -1,6 +1,7 @@
+use crate::utils::Base58Utils;
 use crate::withdraw::Coupon;
 
-use candid::{Nat, Principal};
+use candid::{CandidType, Nat, Principal};
 use minicbor::{Decode, Encode};
 use num_bigint::BigUint;
 use serde::Serialize;
@@ -26,41 +27,135 @@ impl Retriable {
     }
 }
 
-#[derive(Debug, Encode, Decode, PartialEq, Clone, Eq)]
+#[derive(Debug, Encode, Decode, PartialEq, Clone, Eq, Serialize)]
 pub struct SolanaSignatureRange {
     #[n(0)]
     pub before_sol_sig: String,
+    // None scans all the way back to the first transaction of the contract
     #[n(1)]
-    pub until_sol_sig: String,
+    pub until_sol_sig: Option<String>,
     #[n(2)]
     pub retry: Retriable,
+    // contract whose signatures are scanned, None for the primary solana_contract_address
+    #[n(3)]
+    pub contract_address: Option<String>,
 }
 
 impl SolanaSignatureRange {
     // Constructor function to create a new SolanaSignatureRange
-    pub fn new(before: String, until: String) -> Self {
+    pub fn new(before: String, until: Option<String>) -> Self {
         SolanaSignatureRange {
             before_sol_sig: before,
             until_sol_sig: until,
             retry: Retriable(0),
+            contract_address: None,
         }
     }
+
+    pub fn with_contract_address(mut self, contract_address: Option<String>) -> Self {
+        self.contract_address = contract_address;
+        self
+    }
+
+    pub fn until_as_str(&self) -> &str {
+        self.until_sol_sig.as_deref().unwrap_or(GENESIS)
+    }
 }
 
-#[derive(Debug, Encode, Decode, PartialEq, Clone, Eq)]
+// Printed in place of a missing `until` signature.
+pub const GENESIS: &str = "genesis";
+
+#[derive(Debug, Encode, Decode, PartialEq, Clone, Eq, Serialize)]
 pub struct SolanaSignature {
     #[n(0)]
     pub sol_sig: String,
     #[n(1)]
     pub retry: Retriable,
+    // slot the transaction was processed in, unknown for range boundaries
+    #[n(2)]
+    pub slot: Option<u64>,
+    // block time (s) reported with the signature, unknown for range boundaries and older events
+    #[n(3)]
+    pub block_time: Option<u64>,
+    // memo of the transaction as reported by getSignaturesForAddress, e.g. "[26] <principal>"
+    #[n(4)]
+    pub memo: Option<String>,
+}
+
+// Solana signatures are base58 encoded 64 byte ed25519 signatures.
+const SOLANA_SIGNATURE_LENGTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolanaSignatureError {
+    InvalidBase58(String),
+    InvalidLength { sig: String, length: usize },
+}
+
+impl std::fmt::Display for SolanaSignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolanaSignatureError::InvalidBase58(sig) => {
+                write!(f, "Signature {sig} is not valid base58")
+            }
+            SolanaSignatureError::InvalidLength { sig, length } => {
+                write!(
+                    f,
+                    "Signature {sig} is {length} bytes long, expected {SOLANA_SIGNATURE_LENGTH}"
+                )
+            }
+        }
+    }
 }
 
 impl SolanaSignature {
     // Constructor function to create a new SolanaSignature
-    pub fn new(signature: String) -> Self {
+    pub fn new(signature: String, slot: Option<u64>) -> Self {
         SolanaSignature {
             sol_sig: signature,
             retry: Retriable(0),
+            slot,
+            block_time: None,
+            memo: None,
+        }
+    }
+
+    pub fn with_block_time(mut self, block_time: Option<u64>) -> Self {
+        self.block_time = block_time;
+        self
+    }
+
+    pub fn with_memo(mut self, memo: Option<String>) -> Self {
+        self.memo = memo;
+        self
+    }
+
+    // Principals named by the memos of the transaction. Memos are reported joined by "; ",
+    // each prefixed with its length in brackets; memos that are not principals are skipped.
+    pub fn memo_principals(&self) -> Vec<Principal> {
+        let memo = match &self.memo {
+            Some(memo) => memo,
+            None => return vec![],
+        };
+        memo.split("; ")
+            .map(|memo| match memo.split_once("] ") {
+                Some((length, text)) if length.starts_with('[') => text,
+                _ => memo,
+            })
+            .filter_map(|text| Principal::from_text(text.trim()).ok())
+            .collect()
+    }
+
+    // Validating constructor for signatures received from RPC providers.
+    pub fn try_new(signature: &str, slot: Option<u64>) -> Result<Self, SolanaSignatureError> {
+        match Base58Utils::decode(signature) {
+            Some(bytes) if bytes.len() == SOLANA_SIGNATURE_LENGTH => {
+                Ok(Self::new(signature.to_string(), slot))
+            }
+            Some(bytes) => Err(SolanaSignatureError::InvalidLength {
+                sig: signature.to_string(),
+                length: bytes.len(),
+            }),
+            None => Err(SolanaSignatureError::InvalidBase58(signature.to_string())),
         }
     }
 }
@@ -71,13 +166,28 @@ impl std::fmt::Display for SolanaSignature {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
 pub enum DepositEventError {
     InvalidBase64Data,
     InvalidPrincipal,
+    // recipient is the anonymous principal or the minter itself
+    ForbiddenRecipient(Principal),
+    // data length does not match the layout of the deposit event
+    InvalidDataLength { expected: u64, actual: u64 },
+    // the memo of the transaction names another recipient than the deposit data
+    MemoRecipientMismatch { memo: Principal, data: Principal },
     // other variants if needed
 }
 
+// Layout of the deposit event data emitted by the Solana program (borsh):
+// 8 bytes event discriminator, 4 bytes little endian length of the principal text,
+// the principal text and 8 bytes little endian amount of lamports.
+const DEPOSIT_DISCRIMINATOR_LENGTH: usize = 8;
+const DEPOSIT_PRINCIPAL_LENGTH_PREFIX: usize = 4;
+const DEPOSIT_AMOUNT_LENGTH: usize = 8;
+const DEPOSIT_FIXED_LENGTH: usize =
+    DEPOSIT_DISCRIMINATOR_LENGTH + DEPOSIT_PRINCIPAL_LENGTH_PREFIX + DEPOSIT_AMOUNT_LENGTH;
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Encode, Decode, Serialize)]
 pub struct DepositEvent {
     #[n(0)]
@@ -94,6 +204,9 @@ pub struct DepositEvent {
     icp_mint_block_index: Option<u64>,
     #[n(6)]
     pub retry: Retriable,
+    // slot of the deposit transaction, deposits are minted in ascending slot order
+    #[n(7)]
+    pub slot: Option<u64>,
 }
 
 impl DepositEvent {
@@ -102,12 +215,54 @@ impl DepositEvent {
         sol_sig: &str,
         from_address: &str,
         encode_data: &str,
+        slot: u64,
+    ) -> Result<Self, DepositEventError> {
+        Self::decode(
+            deposit_id,
+            sol_sig,
+            from_address,
+            encode_data,
+            slot,
+            ic_cdk::id(),
+        )
+    }
+
+    // `new` with the id of the minter passed in, the recipient may not be the minter.
+    fn decode(
+        deposit_id: u64,
+        sol_sig: &str,
+        from_address: &str,
+        encode_data: &str,
+        slot: u64,
+        minter_id: Principal,
     ) -> Result<Self, DepositEventError> {
         use base64::prelude::*;
 
         let bytes = BASE64_STANDARD
             .decode(encode_data)
             .map_err(|_| DepositEventError::InvalidBase64Data)?;
+
+        // the slicing below silently produces garbage on any other layout
+        if bytes.len() < DEPOSIT_FIXED_LENGTH {
+            return Err(DepositEventError::InvalidDataLength {
+                expected: DEPOSIT_FIXED_LENGTH as u64,
+                actual: bytes.len() as u64,
+            });
+        }
+        let prefix_start = DEPOSIT_DISCRIMINATOR_LENGTH;
+        let principal_length = u32::from_le_bytes(
+            bytes[prefix_start..prefix_start + DEPOSIT_PRINCIPAL_LENGTH_PREFIX]
+                .try_into()
+                .expect("slice of the length prefix size"),
+        ) as usize;
+        let expected = DEPOSIT_FIXED_LENGTH + principal_length;
+        if bytes.len() != expected {
+            return Err(DepositEventError::InvalidDataLength {
+                expected: expected as u64,
+                actual: bytes.len() as u64,
+            });
+        }
+
         let amount_bytes = &bytes[bytes.len() - 8..];
         let mut value: BigUint = BigUint::default(); // Initialize BigUint to 0
         for i in 0..8 {
@@ -116,11 +271,17 @@ impl DepositEvent {
             value |= &shifted_value;
         }
 
-        let address_bytes = &bytes[12..bytes.len() - 8];
+        let address_bytes = &bytes[DEPOSIT_DISCRIMINATOR_LENGTH + DEPOSIT_PRINCIPAL_LENGTH_PREFIX
+            ..bytes.len() - DEPOSIT_AMOUNT_LENGTH];
         let address_hex = String::from_utf8_lossy(&address_bytes);
         let principal = Principal::from_text(address_hex.trim())
             .map_err(|_| DepositEventError::InvalidPrincipal)?;
 
+        // gSOL minted to the anonymous principal is unusable and the minter must never mint to itself
+        if principal == Principal::anonymous() || principal == minter_id {
+            return Err(DepositEventError::ForbiddenRecipient(principal));
+        }
+
         Ok(DepositEvent {
             id: deposit_id,
             from_sol_address: from_address.to_string(),
@@ -129,6 +290,7 @@ impl DepositEvent {
             sol_sig: sol_sig.to_string(),
             icp_mint_block_index: None,
             retry: Retriable(0),
+            slot: Some(slot),
         })
     }
 
@@ -141,6 +303,29 @@ impl DepositEvent {
     }
 }
 
+#[cfg(test)]
+impl DepositEvent {
+    // Deposit as parsed by `new`, without decoding the transaction data.
+    pub fn new_for_test(
+        deposit_id: u64,
+        sol_sig: &str,
+        to_icp_address: Principal,
+        amount: u64,
+        slot: Option<u64>,
+    ) -> Self {
+        DepositEvent {
+            id: deposit_id,
+            from_sol_address: "HS6NTv6GBVSLct8dsimRWRvjczJTAgfgDJt8VpR8wtGm".to_string(),
+            to_icp_address,
+            amount: Nat::from(amount),
+            sol_sig: sol_sig.to_string(),
+            icp_mint_block_index: None,
+            retry: Retriable(0),
+            slot,
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Encode, Decode, Serialize)]
 pub struct WithdrawalEvent {
     #[cbor(n(1), with = "crate::cbor::principal")]
@@ -161,10 +346,22 @@ pub struct WithdrawalEvent {
     #[n(7)]
     #[serde(skip_serializing)]
     pub retry: Retriable,
+    // withdrawal fee kept by the minter, withdrawals burned before fees existed have none
+    #[cbor(n(8), with = "crate::cbor::nat::option")]
+    fee: Option<Nat>,
+    // Solana transaction that redeemed the coupon, reported by an authorized caller
+    #[n(9)]
+    redemption_sol_sig: Option<String>,
 }
 
 impl WithdrawalEvent {
-    pub fn new(burn_id: u64, from: Principal, to_sol_address: String, amount: Nat) -> Self {
+    pub fn new(
+        burn_id: u64,
+        from: Principal,
+        to_sol_address: String,
+        amount: Nat,
+        fee: Nat,
+    ) -> Self {
         WithdrawalEvent {
             from_icp_address: from,
             to_sol_address,
@@ -174,6 +371,20 @@ impl WithdrawalEvent {
             icp_burn_block_index: None,
             coupon: None,
             retry: Retriable(0),
+            fee: Some(fee),
+            redemption_sol_sig: None,
+        }
+    }
+
+    pub fn get_fee(&self) -> Option<&Nat> {
+        self.fee.as_ref()
+    }
+
+    // Amount the coupon is signed for: the burned amount minus the withdrawal fee.
+    pub fn coupon_amount(&self) -> Nat {
+        match &self.fee {
+            Some(fee) => Nat(&self.amount.0 - &fee.0),
+            None => self.amount.clone(),
         }
     }
 
@@ -201,4 +412,126 @@ impl WithdrawalEvent {
     pub fn get_coupon(&self) -> Option<&Coupon> {
         self.coupon.as_ref()
     }
+
+    pub fn update_redemption_sol_sig(&mut self, sol_sig: String) {
+        self.redemption_sol_sig = Some(sol_sig);
+    }
+
+    pub fn get_redemption_sol_sig(&self) -> Option<&String> {
+        self.redemption_sol_sig.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{principal, SOLANA_ADDRESS};
+
+    const SIGNATURE: &str =
+        "4ZxHBTGAKbRR2h6d5qqamGRndpnpbFk9PSBCq5WC2B9pGHeHjTxuaBjyqRyaKQExSSHRbHfdpRZrTgiwxX8Y4Qw5";
+
+    // Base64 deposit event data as emitted by the Solana program.
+    fn deposit_data(to: &Principal, lamports: u64) -> String {
+        use base64::prelude::*;
+
+        let to = to.to_text();
+        let mut bytes = vec![0xd2; DEPOSIT_DISCRIMINATOR_LENGTH];
+        bytes.extend((to.len() as u32).to_le_bytes());
+        bytes.extend(to.as_bytes());
+        bytes.extend(lamports.to_le_bytes());
+        BASE64_STANDARD.encode(bytes)
+    }
+
+    fn decode(data: &str) -> Result<DepositEvent, DepositEventError> {
+        DepositEvent::decode(0, SIGNATURE, SOLANA_ADDRESS, data, 42, principal(0))
+    }
+
+    #[test]
+    fn should_accept_valid_signature() {
+        let signature = SolanaSignature::try_new(SIGNATURE, Some(42)).unwrap();
+
+        assert_eq!(signature.sol_sig, SIGNATURE);
+        assert_eq!(signature.slot, Some(42));
+    }
+
+    #[test]
+    fn should_reject_invalid_signatures() {
+        // 0, O, I and l are not part of the base58 alphabet
+        let not_base58 = SIGNATURE.replace('4', "0");
+        assert_eq!(
+            SolanaSignature::try_new(&not_base58, None),
+            Err(SolanaSignatureError::InvalidBase58(not_base58.clone()))
+        );
+        // an address is valid base58, but only 32 bytes long
+        assert_eq!(
+            SolanaSignature::try_new(SOLANA_ADDRESS, None),
+            Err(SolanaSignatureError::InvalidLength {
+                sig: SOLANA_ADDRESS.to_string(),
+                length: 32,
+            })
+        );
+        assert_eq!(
+            SolanaSignature::try_new("", None),
+            Err(SolanaSignatureError::InvalidLength {
+                sig: String::new(),
+                length: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn should_decode_deposit_data() {
+        let deposit = decode(&deposit_data(&principal(1), 1_500_000_000)).unwrap();
+
+        assert_eq!(deposit.to_icp_address, principal(1));
+        assert_eq!(deposit.amount, Nat::from(1_500_000_000u64));
+        assert_eq!(deposit.slot, Some(42));
+    }
+
+    #[test]
+    fn should_reject_anonymous_recipient() {
+        assert_eq!(
+            decode(&deposit_data(&Principal::anonymous(), 1_000)),
+            Err(DepositEventError::ForbiddenRecipient(Principal::anonymous()))
+        );
+    }
+
+    #[test]
+    fn should_reject_minter_as_recipient() {
+        assert_eq!(
+            decode(&deposit_data(&principal(0), 1_000)),
+            Err(DepositEventError::ForbiddenRecipient(principal(0)))
+        );
+    }
+
+    #[test]
+    fn should_reject_deposit_data_of_another_length() {
+        use base64::prelude::*;
+
+        let bytes = BASE64_STANDARD
+            .decode(deposit_data(&principal(1), 1_000))
+            .unwrap();
+        let text_length = principal(1).to_text().len() as u64;
+
+        // shorter than the fixed part of the layout
+        assert_eq!(
+            decode(&BASE64_STANDARD.encode(&bytes[..19])),
+            Err(DepositEventError::InvalidDataLength {
+                expected: DEPOSIT_FIXED_LENGTH as u64,
+                actual: 19,
+            })
+        );
+        // the principal is shorter or longer than its length prefix
+        for actual in [bytes.len() - 1, bytes.len() + 1] {
+            let mut resized = bytes.clone();
+            resized.resize(actual, 0);
+            assert_eq!(
+                decode(&BASE64_STANDARD.encode(resized)),
+                Err(DepositEventError::InvalidDataLength {
+                    expected: DEPOSIT_FIXED_LENGTH as u64 + text_length,
+                    actual: actual as u64,
+                })
+            );
+        }
+    }
 }
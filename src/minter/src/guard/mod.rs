@@ -68,14 +68,47 @@ pub fn retrieve_sol_guard(
     Guard::new(principal)
 }
 
+/// Guards coupon generation from being executed twice for the same burn_id,
+/// while coupons for distinct burn_ids can be generated in parallel.
+#[must_use]
+#[derive(Debug, PartialEq, Eq)]
+pub struct CouponGuard {
+    burn_id: u64,
+}
+
+impl CouponGuard {
+    fn new(burn_id: u64) -> Result<Self, GuardError> {
+        mutate_state(|s| {
+            if !s.generating_coupons.insert(burn_id) {
+                return Err(GuardError::AlreadyProcessing);
+            }
+            Ok(Self { burn_id })
+        })
+    }
+}
+
+impl Drop for CouponGuard {
+    fn drop(&mut self) {
+        mutate_state(|s| {
+            s.generating_coupons.remove(&self.burn_id);
+        });
+    }
+}
+
+pub fn retrieve_coupon_guard(burn_id: u64) -> Result<CouponGuard, GuardError> {
+    CouponGuard::new(burn_id)
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum TimerGuardError {
     AlreadyProcessing,
 }
 
+/// Guards a timer task from running concurrently and records its metrics once it completes.
 #[derive(Debug, PartialEq, Eq)]
 pub struct TimerGuard {
     task: TaskType,
+    started_at: u64,
 }
 
 impl TimerGuard {
@@ -84,15 +117,20 @@ impl TimerGuard {
             if !s.active_tasks.insert(task) {
                 return Err(TimerGuardError::AlreadyProcessing);
             }
-            Ok(Self { task })
+            Ok(Self {
+                task,
+                started_at: ic_cdk::api::time(),
+            })
         })
     }
 }
 
 impl Drop for TimerGuard {
     fn drop(&mut self) {
-        mutate_state(|s| {
-            s.active_tasks.remove(&self.task);
-        });
+        // instructions of the whole call context, tasks span several messages across awaits
+        let instructions = ic_cdk::api::performance_counter(1);
+        let now = ic_cdk::api::time();
+
+        mutate_state(|s| s.record_task_run(self.task, self.started_at, now, instructions));
     }
 }
@@ -8,5 +8,7 @@ pub mod logs;
 pub mod sol_rpc_client;
 pub mod state;
 pub mod storage;
+#[cfg(test)]
+mod test_fixtures;
 pub mod utils;
 pub mod withdraw;
@@ -1,25 +1,48 @@
 use minter::{
     constants::{
-        GET_LATEST_SOLANA_SIGNATURE, MINT_GSOL, SCRAPPING_SOLANA_SIGNATURES,
+        GET_LATEST_SOLANA_SIGNATURE, MAX_DEPOSIT_ERRORS, MAX_EVENTS_PAGE_SIZE,
+        MAX_PENDING_WITHDRAWALS, MAX_TIMER_JITTER, MINT_GSOL, SCRAPPING_SOLANA_SIGNATURES,
         SCRAPPING_SOLANA_SIGNATURE_RANGES,
     },
-    deposit::{get_latest_signature, mint_gsol, scrap_signature_range, scrap_signatures},
-    lifecycle::{post_upgrade as lifecycle_post_upgrade, MinterArg},
+    deposit::{
+        force_mint as force_mint_deposits, get_latest_signature, mint_gsol, scrap_signature_range,
+        scrap_signatures, DepositError, ForceMintResult,
+    },
+    events::SolanaSignature,
+    lifecycle::{
+        ensure_not_initialized, post_upgrade as lifecycle_post_upgrade, MinterArg, SolanaNetwork,
+    },
     logs::INFO,
+    sol_rpc_client::{
+        normalize_json_rpc_errors, round_slot_result, DecodedLedgerMemo, LastRpcError, LedgerMemo,
+        ProviderHealth, SolRpcClient, ROUND_SLOT_CONTEXT,
+    },
     // sol_rpc_client::types::Error,
-    state::{event::EventType, lazy_call_ecdsa_public_key, read_state, State, STATE},
+    state::{
+        audit::process_event,
+        event::{DepositInfo, EventType, MinterEvent},
+        lazy_call_ecdsa_public_key, mutate_state, read_state, ChainLag, EncodedPublicKey,
+        HealthStatus, MinterConfig, MinterSolanaAddress, SignatureRangeProgress, State, TaskStatus,
+        STATE,
+    },
     storage,
     withdraw::{
-        get_coupon as get_or_regen_coupon, get_withdraw_info as get_user_withdraw_info,
-        withdraw_gsol, Coupon, CouponError, UserWithdrawInfo, WithdrawError,
+        check_minimum_withdrawal_amount, find_withdrawals_by_sol_address as find_sol_withdrawals,
+        generate_all_coupons as generate_user_coupons,
+        get_burn_events_since as get_user_burn_events, get_coupon as get_or_regen_coupon,
+        get_coupon_message, get_coupon_signature, get_pending_withdrawals,
+        get_withdraw_info as get_user_withdraw_info, get_withdrawal_by_burn_id as get_burn_event,
+        self_test as run_self_test, simulate_withdraw as simulate_user_withdraw, withdraw_all_gsol,
+        withdraw_gsol, BurnEvent, BurnEvents, Coupon, CouponError, CouponMessage, CouponSignature,
+        PendingWithdrawals, SelfTestReport, UserWithdrawInfo, WithdrawError,
+        WithdrawalEventWithoutCbor,
     },
 };
 
 use candid::candid_method;
 use ic_cdk::api::management_canister::http_request::{HttpResponse, TransformArgs};
 use ic_cdk_macros::{init, post_upgrade, pre_upgrade, query, update};
-use num_bigint::BigUint;
-use std::time::Duration;
+use std::{future::Future, time::Duration};
 
 /// Sets up timers for various tasks, such as fetching latest signatures and scraping logs.
 fn setup_timers() {
@@ -30,41 +53,54 @@ fn setup_timers() {
         });
     });
 
-    // Set timers for scraping logs and other operations with specified intervals.
-    // These timers are started immediately after installation.
+    // The ledger fee can only be compared with minimum_withdrawal_amount asynchronously.
     ic_cdk_timers::set_timer(Duration::from_secs(0), || {
-        ic_cdk::spawn(async {
-            get_latest_signature().await;
-            scrap_signature_range().await;
-            scrap_signatures().await;
-            mint_gsol().await;
-        });
+        ic_cdk::spawn(check_minimum_withdrawal_amount())
     });
 
-    // Set intervals for periodic tasks.
-    ic_cdk_timers::set_timer_interval(GET_LATEST_SOLANA_SIGNATURE, || {
-        ic_cdk::spawn(async {
-            get_latest_signature().await;
-        });
-    });
+    // Each task starts after its own pseudo-random delay and then runs on its fixed interval.
+    // True randomness is not available synchronously, so the canister time is used as a seed.
+    let seed = ic_cdk::api::time();
+
+    schedule_task(
+        jitter(seed, 0),
+        GET_LATEST_SOLANA_SIGNATURE,
+        get_latest_signature,
+    );
+    schedule_task(
+        jitter(seed, 1),
+        SCRAPPING_SOLANA_SIGNATURE_RANGES,
+        scrap_signature_range,
+    );
+    schedule_task(
+        jitter(seed, 2),
+        SCRAPPING_SOLANA_SIGNATURES,
+        scrap_signatures,
+    );
+    schedule_task(jitter(seed, 3), MINT_GSOL, mint_gsol);
+}
 
-    ic_cdk_timers::set_timer_interval(SCRAPPING_SOLANA_SIGNATURE_RANGES, || {
-        ic_cdk::spawn(async {
-            scrap_signature_range().await;
-        });
+/// Runs `task` once after `delay` and then periodically every `interval`.
+fn schedule_task<F, Fut>(delay: Duration, interval: Duration, task: F)
+where
+    F: Fn() -> Fut + Copy + 'static,
+    Fut: Future<Output = ()> + 'static,
+{
+    ic_cdk_timers::set_timer(delay, move || {
+        ic_cdk::spawn(task());
+        ic_cdk_timers::set_timer_interval(interval, move || ic_cdk::spawn(task()));
     });
+}
 
-    ic_cdk_timers::set_timer_interval(SCRAPPING_SOLANA_SIGNATURES, || {
-        ic_cdk::spawn(async {
-            scrap_signatures().await;
-        });
-    });
+/// Derives a per-task delay in `[0, MAX_TIMER_JITTER)` from the seed.
+fn jitter(seed: u64, task_index: u64) -> Duration {
+    // splitmix64 step to spread consecutive seeds and task indexes
+    let mut x = seed.wrapping_add(task_index.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^= x >> 31;
 
-    ic_cdk_timers::set_timer_interval(MINT_GSOL, || {
-        ic_cdk::spawn(async {
-            mint_gsol().await;
-        });
-    });
+    Duration::from_millis(x % MAX_TIMER_JITTER.as_millis() as u64)
 }
 
 /// Initializes the Minter canister with the given arguments.
@@ -79,6 +115,10 @@ pub fn init(args: MinterArg) {
     match args {
         // If the argument is an initialization argument, initialize the state.
         MinterArg::Init(init_arg) => {
+            if let Err(msg) = ensure_not_initialized() {
+                ic_cdk::trap(msg);
+            }
+
             ic_canister_log::log!(INFO, "\ninitialized minter with arg:\n{init_arg:?}");
             STATE.with(|cell| {
                 storage::record_event(EventType::Init(init_arg.clone()));
@@ -126,24 +166,95 @@ fn post_upgrade(minter_arg: Option<MinterArg>) {
 /// Returns the compressed and uncompressed public keys.
 #[update]
 pub async fn get_address() -> (String, String) {
+    // the key is fetched on demand if the post-install timer has not run yet
+    lazy_call_ecdsa_public_key().await;
+
     read_state(|s| (s.compressed_public_key(), s.uncompressed_public_key()))
 }
 
+/// Returns the public key DER encoded (SubjectPublicKeyInfo) and as PEM.
+#[update]
+pub async fn get_public_key_der() -> EncodedPublicKey {
+    // the key is fetched on demand if the post-install timer has not run yet
+    let public_key = lazy_call_ecdsa_public_key().await;
+
+    EncodedPublicKey::from(&public_key)
+}
+
+/// Returns the principal of the minter canister, e.g. for ledger approvals.
+#[query]
+fn get_minter_principal() -> candid::Principal {
+    ic_cdk::id()
+}
+
+/// Returns the Solana program deposits are sent to and the minter's public key base58 encoded,
+/// in the 64 byte form the program recovers from coupon signatures.
+#[update]
+async fn minter_solana_address() -> MinterSolanaAddress {
+    // the key is fetched on demand if the post-install timer has not run yet
+    lazy_call_ecdsa_public_key().await;
+
+    read_state(State::minter_solana_address)
+}
+
 /// Withdraws GSOL tokens to the specified Solana address.
 ///
 /// # Arguments
 ///
 /// * `solana_address` - The Solana address to withdraw GSOL tokens to.
 /// * `withdraw_amount` - The amount of GSOL tokens to withdraw.
+/// * `from_subaccount` - Optional 32 byte subaccount holding the GSOL tokens.
+/// * `idempotency_key` - Optional 32 byte key chosen by the client. A retry with the same key
+///   returns the coupon of the first burn instead of burning again.
+///
+/// If the gSOL was burned but signing the coupon failed, the error is `SigningWithEcdsaFailed`
+/// or `CouponError`, both carrying the `burn_id` to retry with `get_coupon`.
 #[update]
 async fn withdraw(
     solana_address: String,
     withdraw_amount: candid::Nat,
+    from_subaccount: Option<serde_bytes::ByteBuf>,
+    idempotency_key: Option<serde_bytes::ByteBuf>,
 ) -> Result<Coupon, WithdrawError> {
     let caller = validate_caller_not_anonymous();
-    is_over_limit(&withdraw_amount.0);
 
-    withdraw_gsol(caller, solana_address, withdraw_amount).await
+    withdraw_gsol(
+        caller,
+        from_subaccount.map(|s| s.into_vec()),
+        solana_address,
+        withdraw_amount,
+        idempotency_key.map(|k| k.into_vec()),
+    )
+    .await
+}
+
+/// Withdraws the whole GSOL balance of the caller (minus the ledger fee) to the specified Solana address.
+///
+/// # Arguments
+///
+/// * `solana_address` - The Solana address to withdraw GSOL tokens to.
+#[update]
+async fn withdraw_all(solana_address: String) -> Result<Coupon, WithdrawError> {
+    let caller = validate_caller_not_anonymous();
+
+    withdraw_all_gsol(caller, solana_address).await
+}
+
+/// Dry-runs a withdrawal: validates it and returns the unsigned coupon payload
+/// without touching the ledger or signing anything.
+///
+/// # Arguments
+///
+/// * `solana_address` - The Solana address to withdraw GSOL tokens to.
+/// * `withdraw_amount` - The amount of GSOL tokens to withdraw.
+#[query]
+fn simulate_withdraw(
+    solana_address: String,
+    withdraw_amount: candid::Nat,
+) -> Result<WithdrawalEventWithoutCbor, WithdrawError> {
+    let caller = validate_caller_not_anonymous();
+
+    simulate_user_withdraw(caller, solana_address, withdraw_amount)
 }
 
 /// Gets coupon or tries to regenerate coupon if it is not found.
@@ -153,9 +264,40 @@ async fn withdraw(
 /// * `burn_id` - Burn id of the coupon.
 #[update]
 async fn get_coupon(burn_id: u64) -> Result<Coupon, WithdrawError> {
+    validate_caller_not_anonymous();
+
+    get_or_regen_coupon(burn_id).await
+}
+
+/// Generates coupons for all burned but not yet redeemed withdrawals of the caller,
+/// at most `MAX_COUPONS_PER_CALL` per call, oldest first.
+#[update]
+async fn generate_all_coupons() -> Vec<Result<Coupon, WithdrawError>> {
     let caller = validate_caller_not_anonymous();
 
-    get_or_regen_coupon(caller, burn_id).await
+    generate_user_coupons(caller).await
+}
+
+/// Returns the serialized coupon message and its hash (hex) for a burn id, recomputed
+/// without signing. Useful to debug signature mismatches on Solana.
+///
+/// # Arguments
+///
+/// * `burn_id` - Burn id of the coupon.
+#[query]
+fn get_coupon_message_hash(burn_id: u64) -> Result<CouponMessage, WithdrawError> {
+    get_coupon_message(burn_id)
+}
+
+/// Returns the signature of a generated coupon split into `r`, `s` and the recovery byte `v`,
+/// ready to be used in the Solana redemption transaction.
+///
+/// # Arguments
+///
+/// * `burn_id` - Burn id of the coupon, the coupon has to be generated already.
+#[query]
+fn get_coupon_signature_parts(burn_id: u64) -> Result<CouponSignature, WithdrawError> {
+    get_coupon_signature(burn_id)
 }
 
 /// Returns ledger id.
@@ -172,6 +314,35 @@ async fn get_ledger_id() -> String {
     read_state(|s| s.ledger_id.clone().to_string())
 }
 
+/// Returns the address of the Solana program whose deposits are minted.
+#[query]
+fn get_solana_contract_address() -> String {
+    read_state(|s| s.solana_contract_address.clone())
+}
+
+/// Returns the configuration of the minter, without the RPC URL and headers which may hold API keys.
+#[query]
+fn get_config() -> MinterConfig {
+    read_state(State::config)
+}
+
+/// Returns the Solana network targeted by the minter, derived from the configured RPC URL.
+#[query]
+fn get_solana_network() -> SolanaNetwork {
+    read_state(|s| s.solana_rpc_url().network())
+}
+
+/// Decodes the memo of a ledger block created by the minter into its kind and the
+/// deposit id (mints) or burn id (burns). Returns `null` if the memo was not created by the minter.
+///
+/// # Arguments
+///
+/// * `memo` - Raw memo bytes of the ledger block.
+#[query]
+fn decode_ledger_memo(memo: serde_bytes::ByteBuf) -> Option<DecodedLedgerMemo> {
+    LedgerMemo::decode(&memo)
+}
+
 /// Verification method that validates coupon.
 #[query]
 async fn verify(coupon: Coupon) -> Result<bool, CouponError> {
@@ -186,14 +357,29 @@ async fn verify(coupon: Coupon) -> Result<bool, CouponError> {
 #[query(hidden = true)]
 fn cleanup_response(mut args: TransformArgs) -> HttpResponse {
     // The response header contain non-deterministic fields that make it impossible to reach consensus!
-    // Errors seem deterministic and do not contain data that can break consensus.
+    // JSON-RPC error bodies may carry node specific details as well.
 
     // Clear non-deterministic fields from the response headers.
     args.response.headers.clear();
+    // Error details (`error.data`) differ between nodes, only code and message are kept.
+    args.response.body = normalize_json_rpc_errors(args.response.body);
+    // The chain tip differs between nodes as well, getSlot results are rounded.
+    if args.context == ROUND_SLOT_CONTEXT {
+        args.response.body = round_slot_result(args.response.body);
+    }
 
     args.response
 }
 
+/// Returns a hash of the state rebuilt from the event log. Comparing it before and after an upgrade
+/// confirms the replay produced the same state.
+#[query]
+fn reconstruct_state_hash() -> String {
+    is_controller();
+
+    read_state(State::canonical_hash)
+}
+
 /// Returns the current state of the Minter canister.
 #[query]
 fn get_state() -> String {
@@ -225,12 +411,257 @@ fn get_storage() -> String {
     result
 }
 
-/// Returns active tasks in the Minter canister.
+/// Returns the event at the given log index, in the format of `get_storage`,
+/// or `null` if the index is out of range.
+///
+/// # Arguments
+///
+/// * `index` - Index of the event in the event log.
+#[query]
+fn get_event(index: u64) -> Option<String> {
+    is_controller();
+
+    storage::get_event(index).map(|event| {
+        format!(
+            "Event(timestamp: {}, payload: {:?})",
+            event.timestamp, event.payload
+        )
+    })
+}
+
+/// Exports the event log as JSON for off-canister backups and audits.
+///
+/// # Arguments
+///
+/// * `start` - Event log index to start from, use the last returned `index` + 1 for the next page.
+/// * `length` - Maximum number of events to return, bounded by `MAX_EVENTS_PAGE_SIZE`.
+#[query]
+fn export_events_json(start: u64, length: u64) -> String {
+    is_controller();
+
+    storage::export_events_json(start, length.min(MAX_EVENTS_PAGE_SIZE as u64))
+}
+
+/// Returns accepted/minted deposits and burned/redeemed withdrawals recorded in the event log.
+///
+/// # Arguments
+///
+/// * `start` - Event log index to start from, use the last returned `index` + 1 for the next page.
+/// * `length` - Maximum number of events to return, bounded by `MAX_EVENTS_PAGE_SIZE`.
+#[query]
+fn get_events_paginated(start: u64, length: u64) -> Vec<MinterEvent> {
+    let length = (length as usize).min(MAX_EVENTS_PAGE_SIZE);
+
+    storage::with_event_iter(|events| {
+        events
+            .enumerate()
+            .skip(start as usize)
+            .filter_map(|(index, event)| event.to_minter_event(index as u64))
+            .take(length)
+            .collect()
+    })
+}
+
+/// Returns burned and redeemed withdrawals burned after `since_timestamp` (ns), oldest first,
+/// at most `MAX_BURN_EVENTS_PAGE_SIZE`. To continue a page, pass the `burn_timestamp` and
+/// `burn_id` of its last event, burns sharing that timestamp are then not skipped.
+#[query]
+fn get_burn_events_since(since_timestamp: u64, after_burn_id: Option<u64>) -> BurnEvents {
+    get_user_burn_events(since_timestamp, after_burn_id)
+}
+
+/// Returns the withdrawal with the given burn id, with the Solana signature that redeemed
+/// its coupon if reported.
+#[query]
+fn get_withdrawal_by_burn_id(burn_id: u64) -> Option<BurnEvent> {
+    get_burn_event(burn_id)
+}
+
+/// Records the Solana transaction that redeemed the coupon of a withdrawal, linking the
+/// `burn_id` to the on-chain redemption for reconciliation.
+///
+/// # Arguments
+///
+/// * `burn_id` - Burn id of the redeemed withdrawal.
+/// * `sol_sig` - Signature of the Solana transaction that used the coupon.
+#[update]
+fn record_redemption_signature(burn_id: u64, sol_sig: String) {
+    is_authorized();
+
+    if let Err(err) = SolanaSignature::try_new(&sol_sig, None) {
+        ic_cdk::trap(&err.to_string());
+    }
+    match read_state(|s| s.withdrawal_redeemed_events.get(&burn_id).cloned()) {
+        None => ic_cdk::trap(&format!("no coupon was generated for burn_id {burn_id}")),
+        Some(event) => match event.get_redemption_sol_sig() {
+            Some(recorded) if recorded == &sol_sig => return,
+            Some(recorded) => ic_cdk::trap(&format!(
+                "burn_id {burn_id} was already redeemed by {recorded}"
+            )),
+            None => {}
+        },
+    }
+
+    mutate_state(|s| process_event(s, EventType::RedemptionSignature { burn_id, sol_sig }));
+}
+
+/// Returns withdrawals sent to the given Solana address, oldest first, at most `MAX_BURN_EVENTS_PAGE_SIZE`.
+///
+/// # Arguments
+///
+/// * `addr` - Solana destination address of the withdrawals.
+#[query]
+fn find_withdrawals_by_sol_address(addr: String) -> BurnEvents {
+    is_controller();
+
+    find_sol_withdrawals(&addr)
+}
+
+/// Returns the most recent deposit errors, newest first, keyed by Solana signature.
+///
+/// # Arguments
+///
+/// * `limit` - Maximum number of errors to return.
+#[query]
+fn get_deposit_errors(limit: u64) -> Vec<(String, DepositError)> {
+    is_controller();
+
+    let limit = (limit as usize).min(MAX_DEPOSIT_ERRORS);
+    read_state(|s| s.deposit_errors.iter().rev().take(limit).cloned().collect())
+}
+
+/// Probes every configured Solana RPC provider with `getSlot` and reports its slot and latency,
+/// revealing a dead or lagging provider.
+#[update]
+async fn check_providers_health() -> Vec<ProviderHealth> {
+    is_controller();
+
+    let mut health = vec![];
+    for client in read_state(SolRpcClient::from_state).per_provider() {
+        health.push(client.check_health().await);
+    }
+    health
+}
+
+/// Returns how many slots the last minted deposit is behind the chain tip seen on the last scrape.
+#[query]
+fn get_chain_lag() -> ChainLag {
+    read_state(State::chain_lag)
+}
+
+/// Returns the last failed Solana RPC call with its typed error. Kept in memory only.
+#[query]
+fn get_last_rpc_error() -> Option<LastRpcError> {
+    is_controller();
+
+    read_state(|s| s.last_rpc_error.clone())
+}
+
+/// Returns withdrawals that were burned but never redeemed for a coupon, oldest first.
+///
+/// # Arguments
+///
+/// * `limit` - Maximum number of withdrawals to return, defaults to `MAX_PENDING_WITHDRAWALS`.
+#[query]
+fn get_pending_withdrawals_info(limit: Option<u64>) -> PendingWithdrawals {
+    is_controller();
+
+    let limit = limit
+        .map_or(MAX_PENDING_WITHDRAWALS, |l| l as usize)
+        .min(MAX_PENDING_WITHDRAWALS);
+    get_pending_withdrawals(limit)
+}
+
+/// Returns liveness signals of the bridge for external monitoring.
 #[query]
-fn get_active_tasks() {
+fn health() -> HealthStatus {
+    read_state(|s| s.health(ic_cdk::api::time()))
+}
+
+/// Scrapes the Solana contract history older than `solana_initial_signature`,
+/// down to the first transaction of the contract. Can only be triggered once.
+#[update]
+fn backfill_from_genesis() {
     is_controller();
 
-    read_state(|s| ic_canister_log::log!(INFO, "active_tasks: {:?}", s.active_tasks));
+    if read_state(|s| s.backfill_from_genesis_requested) {
+        ic_cdk::trap("backfill from genesis was already requested");
+    }
+
+    mutate_state(|s| process_event(s, EventType::BackfillFromGenesis));
+}
+
+/// Returns deposits that reached the minting retry limit and are no longer minted.
+#[query]
+fn get_dead_letter_events() -> Vec<DepositInfo> {
+    is_controller();
+
+    read_state(|s| {
+        s.dead_letter_events
+            .values()
+            .map(DepositInfo::from)
+            .collect()
+    })
+}
+
+/// Moves a deposit from the dead letters back to the accepted deposits, with its retries reset.
+///
+/// # Arguments
+///
+/// * `sol_sig` - Solana signature of the deposit.
+#[update]
+fn retry_dead_letter(sol_sig: String) {
+    is_authorized();
+
+    if !read_state(|s| s.dead_letter_events.contains_key(&sol_sig)) {
+        ic_cdk::trap(&format!("no dead letter event for signature {sol_sig}"));
+    }
+
+    mutate_state(|s| process_event(s, EventType::RetryDeadLetterEvent { sol_sig }));
+}
+
+/// Signs a dummy message with the configured ECDSA key, recovers the parity and verifies
+/// the signature, reporting which step failed.
+#[update]
+async fn self_test() -> SelfTestReport {
+    is_controller();
+
+    run_self_test().await
+}
+
+/// Mints up to `limit` confirmed deposits right away, bypassing the circuit breaker,
+/// and reports the outcome of each. Escape hatch for a wedged minting timer.
+#[update]
+async fn force_mint(limit: u32) -> Vec<ForceMintResult> {
+    is_authorized();
+
+    force_mint_deposits(limit as usize).await
+}
+
+/// Resumes minting paused after repeated ledger call failures, before the cooldown ends.
+#[update]
+fn resume_minting() {
+    is_authorized();
+
+    ic_canister_log::log!(INFO, "\nMinting resumed by controller");
+    mutate_state(|s| s.resume_minting());
+}
+
+/// Returns the signature ranges left to scan with the cursor of the running scan.
+#[query]
+fn get_signature_range_progress() -> Vec<SignatureRangeProgress> {
+    is_controller();
+
+    read_state(|s| s.signature_range_progress())
+}
+
+/// Returns the timer tasks of the Minter canister, whether they are running
+/// and the metrics of their last completed run.
+#[query]
+fn get_active_tasks() -> Vec<TaskStatus> {
+    is_controller();
+
+    read_state(|s| s.task_statuses())
 }
 
 fn main() {}
@@ -244,22 +675,22 @@ fn validate_caller_not_anonymous() -> candid::Principal {
     principal
 }
 
-fn is_controller() -> candid::Principal {
+/// Traps unless the caller is a controller or one of the configured `authorized_callers`.
+fn is_authorized() -> candid::Principal {
     let principal = ic_cdk::caller();
-    if !ic_cdk::api::is_controller(&principal) {
-        ic_cdk::trap("only controller can call this method");
+    let is_controller = ic_cdk::api::is_controller(&principal);
+    if !read_state(|s| s.is_authorized_caller(&principal, is_controller)) {
+        ic_cdk::trap("only controller or authorized caller can call this method");
     }
 
     principal
 }
 
-fn is_over_limit(withdraw_amount: &BigUint) {
-    let minimum = read_state(|s| s.minimum_withdrawal_amount.clone());
-
-    match minimum.cmp(&withdraw_amount) {
-        std::cmp::Ordering::Greater => {
-            ic_cdk::trap("withdraw amount is less than minimum withdrawal amount");
-        }
-        _ => {}
+fn is_controller() -> candid::Principal {
+    let principal = ic_cdk::caller();
+    if !ic_cdk::api::is_controller(&principal) {
+        ic_cdk::trap("only controller can call this method");
     }
+
+    principal
 }
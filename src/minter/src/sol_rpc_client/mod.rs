@@ -1,42 +1,277 @@
 use crate::{
-    lifecycle::SolanaRpcUrl,
+    constants::RATE_LIMIT_BACKOFF,
+    lifecycle::{RpcHeader, SolanaRpcUrl},
+    logs::INFO,
     sol_rpc_client::{
         requests::{GetSignaturesForAddressRequestOptions, GetTransactionRequestOptions},
         responses::{GetTransactionResponse, JsonRpcResponse, SignatureResponse},
         types::{
-            ConfirmationStatus, RpcMethod, HEADER_SIZE_LIMIT, SIGNATURE_RESPONSE_SIZE_ESTIMATE,
+            ConfirmationStatus, RpcMethod, HEADER_SIZE_LIMIT, HTTP_MAX_SIZE, MAX_PAYLOAD_SIZE,
+            SIGNATURE_RESPONSE_SIZE_ESTIMATE, SLOT_RESPONSE_SIZE_ESTIMATE, SLOT_ROUNDING,
             TRANSACTION_RESPONSE_SIZE_ESTIMATE,
         },
     },
     state::{mutate_state, read_state, State},
 };
 
+use candid::{CandidType, Nat};
 use ic_cdk::api::{
     call::RejectionCode,
     management_canister::http_request::{
-        http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod, TransformContext,
+        http_request, CanisterHttpRequestArgument, HttpHeader, HttpMethod, HttpResponse,
+        TransformContext,
     },
 };
 use icrc_ledger_types::icrc1::transfer::Memo;
-use serde_json::json;
+use num_traits::ToPrimitive;
+use serde_json::{json, Value};
 use std::collections::HashMap;
 
 pub mod requests;
 pub mod responses;
 pub mod types;
 
+// Sends every request to the primary provider and fails over to the fallback providers in order,
+// so an unreachable provider does not stop progress. Responses of several providers are not
+// reduced to a majority: answers near the chain tip (latest signatures, slot) routinely differ
+// between honest providers, and every call would cost one outcall per provider.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct SolRpcClient {
-    rpc_url: SolanaRpcUrl,
+    providers: Vec<RpcProvider>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct RpcProvider {
+    url: SolanaRpcUrl,
+    // the configured headers usually hold an API key of the primary provider, fallbacks get none
+    headers: Vec<RpcHeader>,
+}
+
+#[derive(CandidType, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum SolRpcError {
     RequestFailed { code: RejectionCode, msg: String },
     JsonRpcFailed { code: i32, msg: String },
     FromUtf8Failed(String),
     FromStringOfJsonFailed(String),
     ToStringOfJsonFailed(String),
+    // the provider responded with HTTP 429 Too Many Requests
+    RateLimited,
+    // the provider responded with a non-200 HTTP status, e.g. during an outage
+    HttpStatus { code: u16, body_excerpt: String },
+    // there is no RPC URL to send the request to
+    NoProvidersConfigured,
+    // the estimated response does not fit into the outcall response limit
+    ResponseTooLarge { estimate: u64, limit: u64 },
+}
+
+#[derive(CandidType, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct LastRpcError {
+    // time (ns) the failed call returned
+    pub timestamp: u64,
+    pub method: String,
+    // scheme and host of the provider that failed, None if the call failed before reaching one
+    pub url: Option<String>,
+    pub error: SolRpcError,
+}
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
+pub struct ProviderHealth {
+    // scheme and host of the provider, the rest of the URL may hold an API key
+    pub url: String,
+    // current slot of the provider, None if the call failed
+    pub slot: Option<u64>,
+    // round trip of the outcall, including consensus among the replicas
+    pub latency_ms: u64,
+    pub ok: bool,
+    pub error: Option<SolRpcError>,
+}
+
+fn record_rpc_error(method: RpcMethod, error: &SolRpcError) {
+    mutate_state(|s| {
+        s.last_rpc_error = Some(LastRpcError {
+            timestamp: ic_cdk::api::time(),
+            method: method.as_str().to_string(),
+            url: s.last_used_rpc_url.as_ref().map(SolanaRpcUrl::redacted),
+            error: error.clone(),
+        })
+    });
+}
+
+// Number of characters of a non-200 response body kept in `SolRpcError::HttpStatus`.
+const BODY_EXCERPT_LENGTH: usize = 256;
+
+// JSON-RPC errors caused by the request itself, repeating the request cannot succeed:
+// invalid request, method not found and invalid params.
+const PERMANENT_JSON_RPC_ERROR_CODES: [i32; 3] = [-32600, -32601, -32602];
+
+// Reported for responses whose envelope does not match the request (JSON-RPC internal error),
+// not a permanent error since another attempt or provider may answer correctly.
+const INVALID_ENVELOPE_ERROR_CODE: i32 = -32603;
+
+const JSON_RPC_VERSION: &str = "2.0";
+
+// Rejects responses that are not JSON-RPC 2.0 or answer a different request.
+fn check_envelope<T>(response: &JsonRpcResponse<T>, request_id: u64) -> Result<(), SolRpcError> {
+    if response.jsonrpc != JSON_RPC_VERSION {
+        return Err(SolRpcError::JsonRpcFailed {
+            code: INVALID_ENVELOPE_ERROR_CODE,
+            msg: format!(
+                "unexpected jsonrpc version {}, expected {JSON_RPC_VERSION}",
+                response.jsonrpc
+            ),
+        });
+    }
+    if response.id != request_id {
+        return Err(SolRpcError::JsonRpcFailed {
+            code: INVALID_ENVELOPE_ERROR_CODE,
+            msg: format!(
+                "response id {} does not match request id {request_id}",
+                response.id
+            ),
+        });
+    }
+    Ok(())
+}
+
+fn request_headers(provider: &RpcProvider) -> Vec<HttpHeader> {
+    let mut headers = vec![HttpHeader {
+        name: "Content-Type".to_string(),
+        value: "application/json".to_string(),
+    }];
+    // provider specific headers, e.g. API keys
+    headers.extend(provider.headers.iter().map(|h| HttpHeader {
+        name: h.name.to_string(),
+        value: h.value.to_string(),
+    }));
+    headers
+}
+
+// Body of a successful HTTP response, other statuses are reported before the body is parsed.
+fn read_response_body(response: HttpResponse) -> Result<String, SolRpcError> {
+    // rate-limited responses carry a non-JSON body, so they are reported before parsing
+    if response.status == Nat::from(429u16) {
+        return Err(SolRpcError::RateLimited);
+    }
+
+    // error pages of the provider are not JSON-RPC responses, report them as such
+    if response.status != Nat::from(200u16) {
+        return Err(SolRpcError::HttpStatus {
+            code: response.status.0.to_u16().unwrap_or(u16::MAX),
+            body_excerpt: String::from_utf8_lossy(&response.body)
+                .chars()
+                .take(BODY_EXCERPT_LENGTH)
+                .collect(),
+        });
+    }
+
+    String::from_utf8(response.body).map_err(|error| SolRpcError::FromUtf8Failed(error.to_string()))
+}
+
+// Parses the (already rounded) body of a getSlot response.
+fn parse_slot_response(response: &str, request_id: u64) -> Result<u64, SolRpcError> {
+    let json_response = serde_json::from_str::<JsonRpcResponse<u64>>(response)
+        .map_err(|error| SolRpcError::FromStringOfJsonFailed(error.to_string()))?;
+    check_envelope(&json_response, request_id)?;
+
+    match (json_response.error, json_response.result) {
+        (Some(error), _) => Err(SolRpcError::JsonRpcFailed {
+            code: error.code,
+            msg: error.message,
+        }),
+        (None, Some(slot)) => Ok(slot),
+        (None, None) => Err(SolRpcError::FromStringOfJsonFailed(
+            "getSlot response holds neither result nor error".to_string(),
+        )),
+    }
+}
+
+// Expected response size of a single item (signature, transaction) of the method: the constant
+// estimate, raised to the largest size observed so far, capped at MAX_PAYLOAD_SIZE.
+fn item_size_estimate(method: RpcMethod) -> u64 {
+    let default = match method {
+        RpcMethod::GetSignaturesForAddress => SIGNATURE_RESPONSE_SIZE_ESTIMATE,
+        RpcMethod::GetTransaction => TRANSACTION_RESPONSE_SIZE_ESTIMATE,
+        RpcMethod::GetSlot => SLOT_RESPONSE_SIZE_ESTIMATE,
+    };
+    read_state(|s| s.observed_response_size(method))
+        .max(default)
+        .min(MAX_PAYLOAD_SIZE)
+}
+
+// Number of transactions whose estimated batch response fits into the outcall response limit.
+fn transaction_batch_size(item_size_estimate: u64) -> usize {
+    (MAX_PAYLOAD_SIZE / item_size_estimate.max(1)).max(1) as usize
+}
+
+fn transactions_response_size_estimate(count: usize, item_size_estimate: u64) -> u64 {
+    (count as u64) * item_size_estimate + HEADER_SIZE_LIMIT
+}
+
+fn record_response_size(method: RpcMethod, response: &str, items: usize) {
+    let item_size = (response.len() as u64).div_ceil(items.max(1) as u64);
+    mutate_state(|s| s.record_response_size(method, item_size));
+}
+
+// A response above `max_response_bytes` is rejected without its size, so the estimate would
+// never learn from it: it is doubled instead, until the response fits or the limit is reached.
+fn grow_size_estimate_on_size_error(method: RpcMethod, error: &SolRpcError) {
+    if error.is_response_size_exceeded() {
+        let item_size = item_size_estimate(method)
+            .saturating_mul(2)
+            .min(MAX_PAYLOAD_SIZE);
+        mutate_state(|s| s.record_response_size(method, item_size));
+    }
+}
+
+impl SolRpcError {
+    // Permanent errors are not retried, everything else (transport, server errors, rate limits)
+    // is considered transient.
+    pub fn is_permanent(&self) -> bool {
+        match self {
+            SolRpcError::JsonRpcFailed { code, .. } => {
+                PERMANENT_JSON_RPC_ERROR_CODES.contains(code)
+            }
+            _ => false,
+        }
+    }
+
+    // The outcall was rejected because the response exceeded `max_response_bytes`, e.g.
+    // "Http body exceeds size limit of 2000000 bytes."
+    pub fn is_response_size_exceeded(&self) -> bool {
+        matches!(self, SolRpcError::RequestFailed { msg, .. } if msg.contains("size limit"))
+    }
+
+    // Failures of the provider itself, the request may succeed on another provider.
+    pub fn is_provider_failure(&self) -> bool {
+        matches!(
+            self,
+            SolRpcError::RequestFailed { .. }
+                | SolRpcError::HttpStatus { .. }
+                | SolRpcError::RateLimited
+        )
+    }
+}
+
+// Calls the providers in order until one does not fail with a provider failure, `on_failover`
+// is told about every failure followed by another attempt. Returns the result of the last call.
+async fn with_failover<'a, T, Fut>(
+    providers: &'a [RpcProvider],
+    mut call: impl FnMut(&'a RpcProvider) -> Fut,
+    mut on_failover: impl FnMut(&'a RpcProvider, &SolRpcError),
+) -> Result<T, SolRpcError>
+where
+    Fut: std::future::Future<Output = Result<T, SolRpcError>>,
+{
+    let mut providers = providers.iter().filter(|p| !p.url.is_empty()).peekable();
+    while let Some(provider) = providers.next() {
+        match call(provider).await {
+            Err(error) if error.is_provider_failure() && providers.peek().is_some() => {
+                on_failover(provider, &error)
+            }
+            result => return result,
+        }
+    }
+    Err(SolRpcError::NoProvidersConfigured)
 }
 
 impl std::fmt::Display for SolRpcError {
@@ -57,24 +292,217 @@ impl std::fmt::Display for SolRpcError {
             SolRpcError::ToStringOfJsonFailed(err) => {
                 write!(f, "To String of JSON failed: {}", err)
             }
+            SolRpcError::RateLimited => {
+                write!(f, "Rate limited by the RPC provider")
+            }
+            SolRpcError::HttpStatus { code, body_excerpt } => {
+                write!(f, "HTTP status {}: {}", code, body_excerpt)
+            }
+            SolRpcError::NoProvidersConfigured => {
+                write!(f, "No Solana RPC provider configured")
+            }
+            SolRpcError::ResponseTooLarge { estimate, limit } => {
+                write!(
+                    f,
+                    "Estimated response of {estimate} bytes exceeds the outcall limit of {limit} bytes"
+                )
+            }
+        }
+    }
+}
+
+// Strips the provider specific `error.data` of JSON-RPC error responses (single or batched),
+// keeping `code` and `message`, so the same error returned by different nodes is identical.
+// Bodies without errors are returned untouched.
+pub fn normalize_json_rpc_errors(body: Vec<u8>) -> Vec<u8> {
+    fn strip_error_data(response: &mut Value) -> bool {
+        match response.get_mut("error").and_then(Value::as_object_mut) {
+            Some(error) => error.remove("data").is_some(),
+            None => false,
+        }
+    }
+
+    let mut json = match serde_json::from_slice::<Value>(&body) {
+        Ok(json) => json,
+        Err(_) => return body,
+    };
+
+    let changed = match &mut json {
+        Value::Array(responses) => responses
+            .iter_mut()
+            .fold(false, |changed, r| strip_error_data(r) || changed),
+        response => strip_error_data(response),
+    };
+
+    match changed {
+        true => serde_json::to_vec(&json).unwrap_or(body),
+        false => body,
+    }
+}
+
+// Transform context asking `cleanup_response` to round the slot of a getSlot response.
+pub const ROUND_SLOT_CONTEXT: &[u8] = b"round_slot";
+
+// Rounds the result of a getSlot response down to a multiple of SLOT_ROUNDING.
+// Error responses and bodies that are not JSON are returned untouched.
+pub fn round_slot_result(body: Vec<u8>) -> Vec<u8> {
+    let mut json = match serde_json::from_slice::<Value>(&body) {
+        Ok(json) => json,
+        Err(_) => return body,
+    };
+
+    match json.get("result").and_then(Value::as_u64) {
+        Some(slot) => {
+            json["result"] = Value::from(slot - slot % SLOT_ROUNDING);
+            serde_json::to_vec(&json).unwrap_or(body)
         }
+        None => body,
+    }
+}
+
+// Encoding of the requested transactions. Deposits are parsed from the `Program data:` log
+// messages, but the response types also expect the plain `json` layout of the message
+// (e.g. account keys as strings), which `jsonParsed` and the binary encodings do not follow.
+// Set explicitly, so a provider with a different default does not break parsing.
+const TRANSACTION_ENCODING: &str = "json";
+
+// Batched getTransaction requests, the position in the batch is the id of each request.
+fn get_transactions_payload(signatures: &[&String]) -> Result<String, SolRpcError> {
+    let mut rpc_request = Vec::new();
+
+    for (position, signature) in signatures.iter().enumerate() {
+        let params: [&dyn erased_serde::Serialize; 2] = [
+            &signature,
+            &GetTransactionRequestOptions {
+                commitment: Some(ConfirmationStatus::Confirmed.as_str().to_string()),
+                encoding: Some(TRANSACTION_ENCODING.to_string()),
+            },
+        ];
+
+        let transaction = json!({
+            "jsonrpc": "2.0",
+            "id": position + 1,
+            "method": RpcMethod::GetTransaction.as_str().to_string(),
+            "params": params,
+        });
+        rpc_request.push(transaction);
     }
+
+    serde_json::to_string(&rpc_request)
+        .map_err(|error| SolRpcError::ToStringOfJsonFailed(error.to_string()))
+}
+
+// Commitment level requested for, and enforced on, the returned signatures.
+const SIGNATURES_COMMITMENT: ConfirmationStatus = ConfirmationStatus::Confirmed;
+
+// Some providers return signatures below the requested commitment level,
+// those are dropped so they are never anchored or scraped.
+fn filter_by_commitment(
+    signatures: Vec<SignatureResponse>,
+    commitment: ConfirmationStatus,
+) -> Vec<SignatureResponse> {
+    let (kept, dropped): (Vec<_>, Vec<_>) = signatures.into_iter().partition(|s| {
+        s.confirmation_status
+            .parse::<ConfirmationStatus>()
+            .is_ok_and(|status| status >= commitment)
+    });
+
+    for signature in &dropped {
+        ic_canister_log::log!(
+            INFO,
+            "\nDropping signature {} with confirmation status {}, expected at least {}",
+            signature.signature,
+            signature.confirmation_status,
+            commitment.as_str()
+        );
+    }
+
+    kept
 }
 
 impl SolRpcClient {
-    const fn new(rpc_url: SolanaRpcUrl) -> Self {
-        Self { rpc_url }
+    const fn new(providers: Vec<RpcProvider>) -> Self {
+        Self { providers }
     }
 
     pub fn from_state(state: &State) -> Self {
-        Self::new(state.solana_rpc_url())
+        Self::new(
+            std::iter::once(RpcProvider {
+                url: state.solana_rpc_url(),
+                headers: state.solana_rpc_headers(),
+            })
+            .chain(
+                state
+                    .fallback_rpc_urls()
+                    .into_iter()
+                    .map(|url| RpcProvider {
+                        url,
+                        headers: vec![],
+                    }),
+            )
+            .collect(),
+        )
+    }
+
+    // One client per configured provider, without failover, e.g. to probe each of them.
+    pub fn per_provider(&self) -> Vec<Self> {
+        self.providers
+            .iter()
+            .map(|provider| Self::new(vec![provider.clone()]))
+            .collect()
     }
 
     async fn rpc_call(
         &self,
+        method: RpcMethod,
         payload: &String,
         effective_size_estimate: u64,
+        transform_context: Vec<u8>,
     ) -> Result<String, SolRpcError> {
+        // empty URLs are skipped, the management canister would reject them anyway
+        let result = with_failover(
+            &self.providers,
+            |provider| {
+                self.http_call(
+                    provider,
+                    payload,
+                    effective_size_estimate,
+                    transform_context.clone(),
+                )
+            },
+            |provider, error| {
+                ic_canister_log::log!(
+                    INFO,
+                    "\n{} failed on {}, failing over to the next provider: {error}",
+                    method.as_str(),
+                    provider.url.redacted()
+                );
+                record_rpc_error(method, error);
+            },
+        )
+        .await;
+
+        if let Err(error) = &result {
+            grow_size_estimate_on_size_error(method, error);
+        }
+
+        // the pause only applies once no provider is left to fail over to
+        if let Err(SolRpcError::RateLimited) = result {
+            let until = ic_cdk::api::time() + RATE_LIMIT_BACKOFF.as_nanos() as u64;
+            mutate_state(|s| s.rate_limited_until = Some(until));
+        }
+        result
+    }
+
+    async fn http_call(
+        &self,
+        provider: &RpcProvider,
+        payload: &String,
+        effective_size_estimate: u64,
+        transform_context: Vec<u8>,
+    ) -> Result<String, SolRpcError> {
+        mutate_state(|s| s.last_used_rpc_url = Some(provider.url.clone()));
+
         // Details of the values used in the following lines can be found here:
         // https://internetcomputer.org/docs/current/developer-docs/production/computation-and-storage-costs
         let base_cycles = 400_000_000u128 + 100_000u128 * (2 * effective_size_estimate as u128);
@@ -84,29 +512,19 @@ impl SolRpcClient {
         let cycles = base_cycles * SUBNET_SIZE / BASE_SUBNET_SIZE;
 
         let request = CanisterHttpRequestArgument {
-            url: self.rpc_url.get().to_string(),
+            url: provider.url.get().to_string(),
             max_response_bytes: Some(effective_size_estimate),
             method: HttpMethod::POST,
-            headers: vec![HttpHeader {
-                name: "Content-Type".to_string(),
-                value: "application/json".to_string(),
-            }],
+            headers: request_headers(provider),
             body: Some(payload.as_bytes().to_vec()),
             transform: Some(TransformContext::from_name(
                 "cleanup_response".to_owned(),
-                vec![],
+                transform_context,
             )),
         };
 
         match http_request(request, cycles).await {
-            Ok((response,)) => {
-                let str_body = String::from_utf8(response.body);
-
-                match str_body {
-                    Ok(str_body) => Ok(str_body),
-                    Err(error) => Err(SolRpcError::FromUtf8Failed(error.to_string())),
-                }
-            }
+            Ok((response,)) => read_response_body(response),
             Err((r, m)) => Err(SolRpcError::RequestFailed { code: r, msg: m }),
         }
     }
@@ -115,23 +533,43 @@ impl SolRpcClient {
     // https://solana.com/docs/rpc/http/getsignaturesforaddress
     pub async fn get_signatures_for_address(
         &self,
+        address: &str,
+        limit: u8,
+        before: Option<&String>,
+        until: Option<&String>,
+    ) -> Result<Vec<SignatureResponse>, SolRpcError> {
+        let result = self
+            .fetch_signatures_for_address(address, limit, before, until)
+            .await;
+        if let Err(error) = &result {
+            record_rpc_error(RpcMethod::GetSignaturesForAddress, error);
+        }
+        result
+    }
+
+    async fn fetch_signatures_for_address(
+        &self,
+        address: &str,
         limit: u8,
         before: Option<&String>,
-        until: &String,
+        until: Option<&String>,
     ) -> Result<Vec<SignatureResponse>, SolRpcError> {
         let params: [&dyn erased_serde::Serialize; 2] = [
-            &read_state(|s| s.solana_contract_address.clone()),
+            &address,
             &GetSignaturesForAddressRequestOptions {
                 limit: Some(limit),
-                commitment: Some(ConfirmationStatus::Confirmed.as_str().to_string()),
+                commitment: Some(SIGNATURES_COMMITMENT.as_str().to_string()),
                 before: before.map(|s| s.to_string()),
-                until: Some(until.to_string()),
+                until: until.map(|s| s.to_string()),
+                // providers lagging behind the last slot we observed are rejected
+                min_context_slot: read_state(|s| s.last_observed_slot),
             },
         ];
 
+        let request_id = mutate_state(State::next_request_id);
         let payload = serde_json::to_string(&json!({
-            "jsonrpc": "2.0",
-            "id": mutate_state(State::next_request_id),
+            "jsonrpc": JSON_RPC_VERSION,
+            "id": request_id,
             "method": RpcMethod::GetSignaturesForAddress.as_str(),
             "params": params
         }));
@@ -144,10 +582,20 @@ impl SolRpcClient {
         // The effective size estimate is the size of the response we expect to get from the RPC
         // Important: all types of transactions are considered here (e.g. withdraw and deposit)
         // This can lead to issues in case new types of transactions are added in the future.
+        let payload_size_estimate =
+            (limit as u64) * item_size_estimate(RpcMethod::GetSignaturesForAddress);
         let effective_size_estimate: u64 =
-            (limit as u64) * SIGNATURE_RESPONSE_SIZE_ESTIMATE + HEADER_SIZE_LIMIT;
+            payload_size_estimate.min(MAX_PAYLOAD_SIZE) + HEADER_SIZE_LIMIT;
 
-        match self.rpc_call(&payload, effective_size_estimate).await {
+        match self
+            .rpc_call(
+                RpcMethod::GetSignaturesForAddress,
+                &payload,
+                effective_size_estimate,
+                vec![],
+            )
+            .await
+        {
             Ok(response) => {
                 let json_response =
                     serde_json::from_str::<JsonRpcResponse<Vec<SignatureResponse>>>(&response);
@@ -155,6 +603,8 @@ impl SolRpcClient {
                 // Check if the response is valid
                 match json_response {
                     Ok(json_response) => {
+                        check_envelope(&json_response, request_id)?;
+
                         // In case error is present in the response ignore the result and return the error
                         if let Some(error) = json_response.error {
                             Err(SolRpcError::JsonRpcFailed {
@@ -162,7 +612,18 @@ impl SolRpcClient {
                                 msg: error.message,
                             })
                         } else {
-                            Ok(json_response.result.unwrap())
+                            let signatures = json_response.result.unwrap();
+                            record_response_size(
+                                RpcMethod::GetSignaturesForAddress,
+                                &response,
+                                signatures.len(),
+                            );
+                            let signatures =
+                                filter_by_commitment(signatures, SIGNATURES_COMMITMENT);
+                            if let Some(slot) = signatures.iter().map(|s| s.slot).max() {
+                                mutate_state(|s| s.record_observed_slot(slot));
+                            }
+                            Ok(signatures)
                         }
                     }
                     Err(error) => {
@@ -174,6 +635,58 @@ impl SolRpcClient {
         }
     }
 
+    // Method relies on the getSlot RPC call to get the slot the provider has reached:
+    // https://solana.com/docs/rpc/http/getslot
+    // The slot is rounded down to a multiple of SLOT_ROUNDING, see `round_slot_result`.
+    pub async fn get_slot(&self) -> Result<u64, SolRpcError> {
+        let result = self.fetch_slot().await;
+        if let Err(error) = &result {
+            record_rpc_error(RpcMethod::GetSlot, error);
+        }
+        result
+    }
+
+    async fn fetch_slot(&self) -> Result<u64, SolRpcError> {
+        let request_id = mutate_state(State::next_request_id);
+        let payload = serde_json::to_string(&json!({
+            "jsonrpc": JSON_RPC_VERSION,
+            "id": request_id,
+            "method": RpcMethod::GetSlot.as_str(),
+            "params": [{ "commitment": SIGNATURES_COMMITMENT.as_str() }]
+        }))
+        .map_err(|error| SolRpcError::ToStringOfJsonFailed(error.to_string()))?;
+
+        let effective_size_estimate = SLOT_RESPONSE_SIZE_ESTIMATE + HEADER_SIZE_LIMIT;
+        let response = self
+            .rpc_call(
+                RpcMethod::GetSlot,
+                &payload,
+                effective_size_estimate,
+                ROUND_SLOT_CONTEXT.to_vec(),
+            )
+            .await?;
+        parse_slot_response(&response, request_id)
+    }
+
+    // Probes the first provider with a getSlot call, see `per_provider`.
+    pub async fn check_health(&self) -> ProviderHealth {
+        let start = ic_cdk::api::time();
+        let result = self.get_slot().await;
+        let latency_ms = ic_cdk::api::time().saturating_sub(start) / 1_000_000;
+
+        ProviderHealth {
+            url: self
+                .providers
+                .first()
+                .map(|provider| provider.url.redacted())
+                .unwrap_or_default(),
+            slot: result.as_ref().ok().copied(),
+            latency_ms,
+            ok: result.is_ok(),
+            error: result.err(),
+        }
+    }
+
     // Method relies on the gettransaction RPC call to get the transaction data:
     // https://solana.com/docs/rpc/http/gettransaction
     // It is using a batch request to get multiple transactions at once.
@@ -182,52 +695,72 @@ impl SolRpcClient {
     //    {"jsonrpc":"2.0","id":1,"method":"getTransaction","params":["1"]}
     //    {"jsonrpc":"2.0","id":2,"method":"getTransaction","params":["2"]}
     // ]' http://localhost:8899
+    // Oversized signature sets are split into batches whose estimated response fits into
+    // the outcall response limit, a failed batch fails the whole call.
     pub async fn get_transactions(
         &self,
         signatures: Vec<&String>,
     ) -> Result<HashMap<String, Result<Option<GetTransactionResponse>, SolRpcError>>, SolRpcError>
     {
-        let mut rpc_request = Vec::new();
+        let mut map = HashMap::with_capacity(signatures.len());
+        let mut remaining = signatures.as_slice();
+        while !remaining.is_empty() {
+            // every response can raise the estimate, so each batch is sized right before it is sent
+            let batch_size = transaction_batch_size(item_size_estimate(RpcMethod::GetTransaction))
+                .min(remaining.len());
+            let (batch, rest) = remaining.split_at(batch_size);
+            match self.get_transactions_batch(batch).await {
+                Ok(transactions) => map.extend(transactions),
+                Err(error) => {
+                    record_rpc_error(RpcMethod::GetTransaction, &error);
+                    return Err(error);
+                }
+            }
+            remaining = rest;
+        }
+        Ok(map)
+    }
 
+    async fn get_transactions_batch(
+        &self,
+        signatures: &[&String],
+    ) -> Result<HashMap<String, Result<Option<GetTransactionResponse>, SolRpcError>>, SolRpcError>
+    {
         // Due to batching request_id cannot be used in the payload.
         // But still need to increment it to count the call.
         mutate_state(State::next_request_id);
 
-        for (position, signature) in signatures.iter().enumerate() {
-            let params: [&dyn erased_serde::Serialize; 2] = [
-                &signature,
-                &GetTransactionRequestOptions {
-                    commitment: Some(ConfirmationStatus::Confirmed.as_str().to_string()),
-                },
-            ];
+        let payload = get_transactions_payload(signatures)?;
 
-            let transaction = json!({
-                "jsonrpc": "2.0",
-                "id": position + 1,
-                "method": RpcMethod::GetTransaction.as_str().to_string(),
-                "params": params,
+        // The effective size estimate is the size of the response we expect to get from the RPC
+        let effective_size_estimate = transactions_response_size_estimate(
+            signatures.len(),
+            item_size_estimate(RpcMethod::GetTransaction),
+        );
+        if effective_size_estimate > HTTP_MAX_SIZE {
+            return Err(SolRpcError::ResponseTooLarge {
+                estimate: effective_size_estimate,
+                limit: HTTP_MAX_SIZE,
             });
-            rpc_request.push(transaction);
         }
 
-        let payload = serde_json::to_string(&rpc_request);
-        let payload = if let Err(error) = payload {
-            return Err(SolRpcError::ToStringOfJsonFailed(error.to_string()));
-        } else {
-            payload.unwrap()
-        };
-
-        // The effective size estimate is the size of the response we expect to get from the RPC
-        let effective_size_estimate: u64 =
-            (signatures.len() as u64) * TRANSACTION_RESPONSE_SIZE_ESTIMATE + HEADER_SIZE_LIMIT;
-
-        match self.rpc_call(&payload, effective_size_estimate).await {
+        match self
+            .rpc_call(
+                RpcMethod::GetTransaction,
+                &payload,
+                effective_size_estimate,
+                vec![],
+            )
+            .await
+        {
             Ok(response) => {
                 let json_responses =
                     serde_json::from_str::<Vec<JsonRpcResponse<GetTransactionResponse>>>(&response);
 
                 match json_responses {
                     Ok(responses) => {
+                        record_response_size(RpcMethod::GetTransaction, &response, responses.len());
+
                         let mut map = HashMap::<
                             String,
                             Result<Option<GetTransactionResponse>, SolRpcError>,
@@ -260,9 +793,31 @@ impl SolRpcClient {
     }
 }
 
+#[derive(
+    CandidType,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Debug,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum MemoKind {
+    Deposit,
+    Withdrawal,
+}
+
 // Memo is limited to 32 bytes in size
-#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, serde::Serialize)]
-pub struct LedgerMemo(pub u64);
+// Format: the CBOR map {"kind": "Deposit" | "Withdrawal", "id": <u64>}, where the id is the
+// deposit id for mints and the burn id for burns (at most 29 bytes).
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, serde::Serialize, serde::Deserialize)]
+pub struct LedgerMemo {
+    pub kind: MemoKind,
+    pub id: u64,
+}
 
 impl From<LedgerMemo> for Memo {
     fn from(memo: LedgerMemo) -> Self {
@@ -270,3 +825,595 @@ impl From<LedgerMemo> for Memo {
         Memo::from(bytes)
     }
 }
+
+#[derive(CandidType, Clone, PartialEq, Eq, Debug)]
+pub struct DecodedLedgerMemo {
+    // None for memos written before the kind was encoded (a bare CBOR integer)
+    pub kind: Option<MemoKind>,
+    pub id: u64,
+}
+
+impl LedgerMemo {
+    pub fn deposit(id: u64) -> Self {
+        Self {
+            kind: MemoKind::Deposit,
+            id,
+        }
+    }
+
+    pub fn withdrawal(id: u64) -> Self {
+        Self {
+            kind: MemoKind::Withdrawal,
+            id,
+        }
+    }
+
+    // Reverses the memo of a ledger block back into the deposit/burn id.
+    pub fn decode(bytes: &[u8]) -> Option<DecodedLedgerMemo> {
+        if let Ok(memo) = serde_cbor::de::from_slice::<LedgerMemo>(bytes) {
+            return Some(DecodedLedgerMemo {
+                kind: Some(memo.kind),
+                id: memo.id,
+            });
+        }
+
+        serde_cbor::de::from_slice::<u64>(bytes)
+            .ok()
+            .map(|id| DecodedLedgerMemo { kind: None, id })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRIMARY: &str = "https://api.mainnet-beta.solana.com";
+    const FALLBACK: &str = "https://rpc.ankr.com/solana";
+
+    // Runs `with_failover` against providers answering with the given results,
+    // returns the result, the called URLs and the URLs failed over from.
+    fn call_providers(
+        responses: Vec<(&str, Result<String, SolRpcError>)>,
+    ) -> (Result<String, SolRpcError>, Vec<String>, Vec<String>) {
+        let providers: Vec<RpcProvider> = responses
+            .iter()
+            .map(|(url, _)| RpcProvider {
+                url: url.to_string().into(),
+                headers: vec![],
+            })
+            .collect();
+        let responses: HashMap<String, Result<String, SolRpcError>> = responses
+            .into_iter()
+            .map(|(url, result)| (url.to_string(), result))
+            .collect();
+        let mut called = vec![];
+        let mut failed_over = vec![];
+
+        let result = futures::executor::block_on(with_failover(
+            &providers,
+            |provider| {
+                called.push(provider.url.to_string());
+                std::future::ready(responses[provider.url.get()].clone())
+            },
+            |provider, _| failed_over.push(provider.url.to_string()),
+        ));
+        (result, called, failed_over)
+    }
+
+    #[test]
+    fn should_not_call_fallback_when_primary_succeeds() {
+        let (result, called, failed_over) = call_providers(vec![
+            (PRIMARY, Ok("primary".to_string())),
+            (FALLBACK, Ok("fallback".to_string())),
+        ]);
+
+        assert_eq!(result, Ok("primary".to_string()));
+        assert_eq!(called, vec![PRIMARY]);
+        assert!(failed_over.is_empty());
+    }
+
+    #[test]
+    fn should_fail_over_to_fallback_when_primary_fails() {
+        for error in [
+            SolRpcError::RequestFailed {
+                code: RejectionCode::SysTransient,
+                msg: "timeout".to_string(),
+            },
+            SolRpcError::HttpStatus {
+                code: 503,
+                body_excerpt: "Service Unavailable".to_string(),
+            },
+            SolRpcError::RateLimited,
+        ] {
+            let (result, called, failed_over) = call_providers(vec![
+                (PRIMARY, Err(error)),
+                (FALLBACK, Ok("fallback".to_string())),
+            ]);
+
+            assert_eq!(result, Ok("fallback".to_string()));
+            assert_eq!(called, vec![PRIMARY, FALLBACK]);
+            assert_eq!(failed_over, vec![PRIMARY]);
+        }
+    }
+
+    #[test]
+    fn should_return_the_last_error_when_every_provider_fails() {
+        let (result, called, failed_over) = call_providers(vec![
+            (PRIMARY, Err(SolRpcError::RateLimited)),
+            (
+                FALLBACK,
+                Err(SolRpcError::HttpStatus {
+                    code: 500,
+                    body_excerpt: "Internal Server Error".to_string(),
+                }),
+            ),
+        ]);
+
+        assert_eq!(
+            result,
+            Err(SolRpcError::HttpStatus {
+                code: 500,
+                body_excerpt: "Internal Server Error".to_string(),
+            })
+        );
+        assert_eq!(called, vec![PRIMARY, FALLBACK]);
+        assert_eq!(failed_over, vec![PRIMARY]);
+    }
+
+    #[test]
+    fn should_progress_with_one_of_three_providers_down() {
+        const SECOND_FALLBACK: &str = "https://solana-mainnet.rpc.extrnode.com";
+        let down = || SolRpcError::RequestFailed {
+            code: RejectionCode::SysTransient,
+            msg: "connection refused".to_string(),
+        };
+
+        for down_position in 0..3 {
+            let responses = [PRIMARY, FALLBACK, SECOND_FALLBACK]
+                .into_iter()
+                .enumerate()
+                .map(|(position, url)| match position == down_position {
+                    true => (url, Err(down())),
+                    false => (url, Ok(url.to_string())),
+                })
+                .collect();
+            let (result, _, _) = call_providers(responses);
+
+            let expected = if down_position == 0 {
+                FALLBACK
+            } else {
+                PRIMARY
+            };
+            assert_eq!(result, Ok(expected.to_string()));
+        }
+    }
+
+    #[test]
+    fn should_not_fail_over_on_errors_of_the_response() {
+        let error = SolRpcError::FromUtf8Failed("invalid utf-8".to_string());
+        let (result, called, _) = call_providers(vec![
+            (PRIMARY, Err(error.clone())),
+            (FALLBACK, Ok("fallback".to_string())),
+        ]);
+
+        assert_eq!(result, Err(error));
+        assert_eq!(called, vec![PRIMARY]);
+    }
+
+    #[test]
+    fn should_split_large_signature_set_into_valid_sized_batches() {
+        let signatures: Vec<String> = (0..10_000).map(|i| format!("sig{i}")).collect();
+
+        for item_size in [TRANSACTION_RESPONSE_SIZE_ESTIMATE, 50_000, MAX_PAYLOAD_SIZE] {
+            let batches: Vec<&[String]> = signatures
+                .chunks(transaction_batch_size(item_size))
+                .collect();
+
+            assert!(batches.len() > 1);
+            for batch in &batches {
+                assert!(
+                    transactions_response_size_estimate(batch.len(), item_size) <= HTTP_MAX_SIZE
+                );
+            }
+            assert_eq!(batches.concat(), signatures);
+        }
+    }
+
+    #[test]
+    fn should_normalize_errors_differing_in_provider_data() {
+        let first = br#"{"jsonrpc":"2.0","error":{"code":-32004,"message":"Block not available for slot 2","data":{"node":"fra-1","ts":1711616761}},"id":1}"#;
+        let second = br#"{"jsonrpc":"2.0","error":{"code":-32004,"message":"Block not available for slot 2","data":{"node":"ams-3","ts":1711616763}},"id":1}"#;
+
+        let first = normalize_json_rpc_errors(first.to_vec());
+        assert_eq!(first, normalize_json_rpc_errors(second.to_vec()));
+
+        let error: JsonRpcResponse<Value> = serde_json::from_slice(&first).unwrap();
+        let error = error.error.unwrap();
+        assert_eq!(error.code, -32004);
+        assert_eq!(error.message, "Block not available for slot 2");
+    }
+
+    #[test]
+    fn should_normalize_errors_of_batched_responses() {
+        let first = br#"[{"jsonrpc":"2.0","result":null,"id":1},{"jsonrpc":"2.0","error":{"code":-32009,"message":"Slot skipped","data":"node 1"},"id":2}]"#;
+        let second = br#"[{"jsonrpc":"2.0","result":null,"id":1},{"jsonrpc":"2.0","error":{"code":-32009,"message":"Slot skipped","data":"node 2"},"id":2}]"#;
+
+        assert_eq!(
+            normalize_json_rpc_errors(first.to_vec()),
+            normalize_json_rpc_errors(second.to_vec())
+        );
+    }
+
+    #[test]
+    fn should_not_touch_responses_without_errors() {
+        let body = br#"{"jsonrpc":"2.0","result":{"data":1},"id":1}"#.to_vec();
+
+        assert_eq!(normalize_json_rpc_errors(body.clone()), body);
+    }
+
+    #[test]
+    fn should_parse_get_slot_response() {
+        let body = r#"{"jsonrpc":"2.0","result":268435519,"id":7}"#;
+
+        assert_eq!(parse_slot_response(body, 7), Ok(268_435_519));
+    }
+
+    #[test]
+    fn should_parse_rounded_get_slot_response() {
+        let body = br#"{"jsonrpc":"2.0","result":268435519,"id":7}"#.to_vec();
+        let rounded = String::from_utf8(round_slot_result(body)).unwrap();
+
+        assert_eq!(parse_slot_response(&rounded, 7), Ok(268_435_500));
+    }
+
+    #[test]
+    fn should_return_get_slot_errors() {
+        let error =
+            r#"{"jsonrpc":"2.0","error":{"code":-32005,"message":"Node is behind"},"id":7}"#;
+        assert_eq!(
+            round_slot_result(error.as_bytes().to_vec()),
+            error.as_bytes().to_vec()
+        );
+        assert_eq!(
+            parse_slot_response(error, 7),
+            Err(SolRpcError::JsonRpcFailed {
+                code: -32005,
+                msg: "Node is behind".to_string(),
+            })
+        );
+
+        let empty = r#"{"jsonrpc":"2.0","id":7}"#;
+        assert!(matches!(
+            parse_slot_response(empty, 7),
+            Err(SolRpcError::FromStringOfJsonFailed(_))
+        ));
+        assert!(matches!(
+            parse_slot_response(r#"{"jsonrpc":"2.0","result":1,"id":8}"#, 7),
+            Err(SolRpcError::JsonRpcFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn should_reject_envelope_of_another_version_or_request() {
+        let envelope = |jsonrpc: &str, id: u64| {
+            serde_json::from_str::<JsonRpcResponse<Vec<SignatureResponse>>>(&format!(
+                r#"{{"jsonrpc":"{jsonrpc}","result":[],"id":{id}}}"#
+            ))
+            .unwrap()
+        };
+
+        assert_eq!(check_envelope(&envelope("2.0", 3), 3), Ok(()));
+        assert_eq!(
+            check_envelope(&envelope("1.0", 3), 3),
+            Err(SolRpcError::JsonRpcFailed {
+                code: INVALID_ENVELOPE_ERROR_CODE,
+                msg: "unexpected jsonrpc version 1.0, expected 2.0".to_string(),
+            })
+        );
+        assert_eq!(
+            check_envelope(&envelope("2.0", 4), 3),
+            Err(SolRpcError::JsonRpcFailed {
+                code: INVALID_ENVELOPE_ERROR_CODE,
+                msg: "response id 4 does not match request id 3".to_string(),
+            })
+        );
+        // another attempt may get a correct answer
+        assert!(!check_envelope(&envelope("2.0", 4), 3)
+            .unwrap_err()
+            .is_permanent());
+    }
+
+    #[test]
+    fn should_attach_configured_headers_to_the_request() {
+        let mut state = crate::test_fixtures::state();
+        state.solana_rpc_headers = vec![RpcHeader {
+            name: "x-api-key".to_string(),
+            value: "secret".to_string(),
+        }];
+        state.fallback_rpc_urls = vec![FALLBACK.to_string().into()];
+        let client = SolRpcClient::from_state(&state);
+
+        let headers: Vec<Vec<(String, String)>> = client
+            .providers
+            .iter()
+            .map(|provider| {
+                request_headers(provider)
+                    .into_iter()
+                    .map(|h| (h.name, h.value))
+                    .collect()
+            })
+            .collect();
+
+        let content_type = ("Content-Type".to_string(), "application/json".to_string());
+        let api_key = ("x-api-key".to_string(), "secret".to_string());
+        // the API key of the primary is never sent to the fallback
+        assert_eq!(
+            headers,
+            vec![vec![content_type.clone(), api_key], vec![content_type]]
+        );
+    }
+
+    fn http_response(status: u16, body: &str) -> HttpResponse {
+        HttpResponse {
+            status: Nat::from(status),
+            headers: vec![],
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn should_map_too_many_requests_to_rate_limited() {
+        let response = http_response(429, "Too many requests for a specific RPC call");
+
+        assert_eq!(read_response_body(response), Err(SolRpcError::RateLimited));
+    }
+
+    #[test]
+    fn should_return_body_of_successful_response() {
+        let body = r#"{"jsonrpc":"2.0","result":1,"id":1}"#;
+
+        assert_eq!(
+            read_response_body(http_response(200, body)),
+            Ok(body.to_string())
+        );
+    }
+
+    #[test]
+    fn should_report_server_errors_before_parsing_the_body() {
+        // a valid JSON-RPC error body must not be mistaken for a response of the node
+        let body = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"internal"},"id":1}"#;
+        assert_eq!(
+            read_response_body(http_response(500, body)),
+            Err(SolRpcError::HttpStatus {
+                code: 500,
+                body_excerpt: body.to_string(),
+            })
+        );
+
+        let response = read_response_body(http_response(503, &"unavailable ".repeat(100)));
+        match response {
+            Err(SolRpcError::HttpStatus { code, body_excerpt }) => {
+                assert_eq!(code, 503);
+                assert_eq!(body_excerpt.chars().count(), BODY_EXCERPT_LENGTH);
+                assert!(body_excerpt.starts_with("unavailable unavailable"));
+            }
+            other => panic!("expected an HTTP status error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn should_drop_signatures_below_the_requested_commitment() {
+        let response = r#"[
+            {"blockTime":1711616790,"confirmationStatus":"finalized","err":null,"memo":null,"signature":"finalized","slot":300},
+            {"blockTime":1711616780,"confirmationStatus":"processed","err":null,"memo":null,"signature":"processed","slot":200},
+            {"blockTime":1711616770,"confirmationStatus":"confirmed","err":null,"memo":null,"signature":"confirmed","slot":100},
+            {"blockTime":1711616760,"confirmationStatus":"unknown","err":null,"memo":null,"signature":"unknown","slot":50}
+        ]"#;
+        let signatures: Vec<SignatureResponse> = serde_json::from_str(response).unwrap();
+
+        let kept: Vec<String> = filter_by_commitment(signatures, ConfirmationStatus::Confirmed)
+            .into_iter()
+            .map(|s| s.signature)
+            .collect();
+
+        assert_eq!(kept, vec!["finalized", "confirmed"]);
+    }
+
+    #[test]
+    fn should_classify_errors_of_the_request_as_permanent() {
+        for code in [-32600, -32601, -32602] {
+            let error = SolRpcError::JsonRpcFailed {
+                code,
+                msg: "Invalid params".to_string(),
+            };
+            assert!(error.is_permanent());
+        }
+    }
+
+    #[test]
+    fn should_classify_server_and_transport_errors_as_transient() {
+        let transient = [
+            SolRpcError::JsonRpcFailed {
+                code: INVALID_ENVELOPE_ERROR_CODE,
+                msg: "response id does not match".to_string(),
+            },
+            SolRpcError::JsonRpcFailed {
+                code: -32005,
+                msg: "Node is behind".to_string(),
+            },
+            SolRpcError::JsonRpcFailed {
+                code: -32004,
+                msg: "Block not available".to_string(),
+            },
+            SolRpcError::RateLimited,
+            SolRpcError::FromStringOfJsonFailed("EOF".to_string()),
+        ];
+
+        for error in transient {
+            assert!(!error.is_permanent(), "{error:?}");
+        }
+    }
+
+    #[test]
+    fn should_round_trip_ledger_memo() {
+        for id in [0, 42, u64::MAX] {
+            let memo = Memo::from(LedgerMemo::deposit(id));
+            assert!(memo.0.len() <= 32);
+
+            assert_eq!(
+                LedgerMemo::decode(&memo.0),
+                Some(DecodedLedgerMemo {
+                    kind: Some(MemoKind::Deposit),
+                    id,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn should_distinguish_deposit_and_withdrawal_memos_of_the_same_id() {
+        let deposit = Memo::from(LedgerMemo::deposit(42));
+        let withdrawal = Memo::from(LedgerMemo::withdrawal(42));
+
+        assert_ne!(deposit, withdrawal);
+        assert_eq!(
+            LedgerMemo::decode(&withdrawal.0).unwrap().kind,
+            Some(MemoKind::Withdrawal)
+        );
+    }
+
+    #[test]
+    fn should_decode_legacy_and_reject_invalid_memos() {
+        let legacy = serde_cbor::ser::to_vec(&7u64).unwrap();
+        assert_eq!(
+            LedgerMemo::decode(&legacy),
+            Some(DecodedLedgerMemo { kind: None, id: 7 })
+        );
+        assert_eq!(LedgerMemo::decode(b"not a memo"), None);
+    }
+
+    #[test]
+    fn should_skip_empty_urls() {
+        let (result, called, _) = call_providers(vec![("", Ok("empty".to_string()))]);
+
+        assert_eq!(result, Err(SolRpcError::NoProvidersConfigured));
+        assert!(called.is_empty());
+    }
+
+    #[test]
+    fn should_fail_without_providers() {
+        let (result, called, _) = call_providers(vec![]);
+
+        assert_eq!(result, Err(SolRpcError::NoProvidersConfigured));
+        assert!(called.is_empty());
+    }
+
+    #[test]
+    fn should_request_transactions_with_json_encoding() {
+        let (first, second) = ("sig1".to_string(), "sig2".to_string());
+        let payload = get_transactions_payload(&[&first, &second]).unwrap();
+
+        let requests: Vec<serde_json::Value> = serde_json::from_str(&payload).unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[1],
+            json!({
+                "jsonrpc": "2.0",
+                "id": 2,
+                "method": "getTransaction",
+                "params": ["sig2", {"commitment": "confirmed", "encoding": "json"}],
+            })
+        );
+    }
+
+    #[test]
+    fn should_raise_size_estimate_to_the_largest_observed_response() {
+        crate::test_fixtures::set_state(crate::test_fixtures::state());
+        let method = RpcMethod::GetTransaction;
+        assert_eq!(
+            item_size_estimate(method),
+            TRANSACTION_RESPONSE_SIZE_ESTIMATE
+        );
+
+        // two transactions of 5_000 bytes each
+        record_response_size(method, &"x".repeat(10_000), 2);
+        assert_eq!(item_size_estimate(method), 5_000);
+        assert!(
+            transaction_batch_size(item_size_estimate(method))
+                < transaction_batch_size(TRANSACTION_RESPONSE_SIZE_ESTIMATE)
+        );
+
+        // smaller responses do not lower the estimate, other methods are not affected
+        record_response_size(method, "small", 1);
+        assert_eq!(item_size_estimate(method), 5_000);
+        assert_eq!(
+            item_size_estimate(RpcMethod::GetSignaturesForAddress),
+            SIGNATURE_RESPONSE_SIZE_ESTIMATE
+        );
+
+        // a single response cannot exceed the outcall limit
+        record_response_size(method, &"x".repeat(MAX_PAYLOAD_SIZE as usize + 1), 1);
+        assert_eq!(item_size_estimate(method), MAX_PAYLOAD_SIZE);
+        assert_eq!(transaction_batch_size(item_size_estimate(method)), 1);
+    }
+
+    #[test]
+    fn should_raise_size_estimate_after_a_response_size_failure() {
+        crate::test_fixtures::set_state(crate::test_fixtures::state());
+        let method = RpcMethod::GetTransaction;
+        let too_large = SolRpcError::RequestFailed {
+            code: RejectionCode::SysFatal,
+            msg: "Http body exceeds size limit of 12000 bytes.".to_string(),
+        };
+
+        // other failures say nothing about the size
+        grow_size_estimate_on_size_error(method, &SolRpcError::RateLimited);
+        assert_eq!(
+            item_size_estimate(method),
+            TRANSACTION_RESPONSE_SIZE_ESTIMATE
+        );
+
+        grow_size_estimate_on_size_error(method, &too_large);
+        assert_eq!(
+            item_size_estimate(method),
+            2 * TRANSACTION_RESPONSE_SIZE_ESTIMATE
+        );
+        assert_eq!(
+            item_size_estimate(RpcMethod::GetSignaturesForAddress),
+            SIGNATURE_RESPONSE_SIZE_ESTIMATE
+        );
+
+        // the estimate stops growing at the outcall limit
+        for _ in 0..64 {
+            grow_size_estimate_on_size_error(method, &too_large);
+        }
+        assert_eq!(item_size_estimate(method), MAX_PAYLOAD_SIZE);
+    }
+
+    #[test]
+    fn should_encode_rpc_errors_with_candid() {
+        for error in [
+            SolRpcError::RequestFailed {
+                code: RejectionCode::SysTransient,
+                msg: "timeout".to_string(),
+            },
+            SolRpcError::HttpStatus {
+                code: 503,
+                body_excerpt: "Service Unavailable".to_string(),
+            },
+            SolRpcError::RateLimited,
+        ] {
+            let last_error = LastRpcError {
+                timestamp: 1_711_616_761_296_437_000,
+                method: RpcMethod::GetTransaction.as_str().to_string(),
+                error,
+            };
+
+            let bytes = candid::encode_one(&last_error).unwrap();
+            assert_eq!(
+                candid::decode_one::<LastRpcError>(&bytes).unwrap(),
+                last_error
+            );
+        }
+    }
+}
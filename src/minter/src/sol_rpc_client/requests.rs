@@ -4,11 +4,43 @@ use serde::{Deserialize, Serialize};
 pub struct GetSignaturesForAddressRequestOptions {
     pub limit: Option<u8>,
     pub commitment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub until: Option<String>,
     pub before: Option<String>,
+    // the minimum slot the provider must have reached to evaluate the request
+    #[serde(rename = "minContextSlot", skip_serializing_if = "Option::is_none")]
+    pub min_context_slot: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct GetTransactionRequestOptions {
     pub commitment: Option<String>,
+    pub encoding: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(until: Option<&str>) -> GetSignaturesForAddressRequestOptions {
+        GetSignaturesForAddressRequestOptions {
+            limit: Some(10),
+            commitment: Some("confirmed".to_string()),
+            until: until.map(str::to_string),
+            before: Some("before".to_string()),
+            min_context_slot: None,
+        }
+    }
+
+    #[test]
+    fn should_omit_until_to_scan_back_to_genesis() {
+        assert_eq!(
+            serde_json::to_string(&options(None)).unwrap(),
+            r#"{"limit":10,"commitment":"confirmed","before":"before"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&options(Some("until"))).unwrap(),
+            r#"{"limit":10,"commitment":"confirmed","until":"until","before":"before"}"#
+        );
+    }
 }
@@ -17,8 +17,9 @@ pub struct JsonRpcResponse<T> {
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct SignatureResponse {
+    // null when the block time is not yet available for the transaction
     #[serde(rename = "blockTime")]
-    pub block_time: u64,
+    pub block_time: Option<u64>,
     #[serde(rename = "confirmationStatus")]
     pub confirmation_status: String,
     pub err: Option<Value>,
@@ -107,3 +108,32 @@ pub struct GetTransactionResponse {
     pub slot: u64,
     pub transaction: Transaction,
 }
+
+impl GetTransactionResponse {
+    // On-chain order of transactions, the signature only breaks ties so the order is total.
+    pub fn chain_order(&self) -> (u64, u64, Option<&String>) {
+        (
+            self.slot,
+            self.block_time,
+            self.transaction.signatures.first(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_deserialize_signature_without_block_time() {
+        let response = r#"{"jsonrpc":"2.0","result":[{"blockTime":null,"confirmationStatus":"confirmed","err":null,"memo":null,"signature":"5h6xBEauJ3PK6SWCZ1PGjBvj8vDdWG3KpwATGy1ARAXFSDwt8GFXM7W5Ncn16wmqokgpiKRLuS83KUxyZyv2sUYv","slot":114}],"id":1}"#;
+
+        let response: JsonRpcResponse<Vec<SignatureResponse>> =
+            serde_json::from_str(response).unwrap();
+        let signatures = response.result.unwrap();
+
+        assert_eq!(signatures.len(), 1);
+        assert_eq!(signatures[0].block_time, None);
+        assert_eq!(signatures[0].slot, 114);
+    }
+}
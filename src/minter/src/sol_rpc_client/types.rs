@@ -17,10 +17,18 @@ pub const SIGNATURE_RESPONSE_SIZE_ESTIMATE: u64 = 500;
 // In case no memo is set transaction object should be around 1100 bytes long.
 pub const TRANSACTION_RESPONSE_SIZE_ESTIMATE: u64 = 2200;
 
-#[derive(Debug, Clone, Copy)]
+// A getSlot response only carries a number, e.g. {"jsonrpc":"2.0","result":1234,"id":1}.
+pub const SLOT_RESPONSE_SIZE_ESTIMATE: u64 = 100;
+
+// Replicas call the provider at slightly different times, so getSlot results are rounded down
+// to a multiple of this in the transform to reach consensus (100 slots are about 40 seconds).
+pub const SLOT_ROUNDING: u64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum RpcMethod {
     GetSignaturesForAddress,
     GetTransaction,
+    GetSlot,
 }
 
 impl RpcMethod {
@@ -28,15 +36,30 @@ impl RpcMethod {
         match self {
             RpcMethod::GetSignaturesForAddress => "getSignaturesForAddress",
             RpcMethod::GetTransaction => "getTransaction",
+            RpcMethod::GetSlot => "getSlot",
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+// Variants are ordered by commitment level: processed < confirmed < finalized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ConfirmationStatus {
-    Finalized,
-    Confirmed,
     Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl std::str::FromStr for ConfirmationStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "finalized" => Ok(ConfirmationStatus::Finalized),
+            "confirmed" => Ok(ConfirmationStatus::Confirmed),
+            "processed" => Ok(ConfirmationStatus::Processed),
+            _ => Err(format!("unknown confirmation status: {s}")),
+        }
+    }
 }
 
 impl ConfirmationStatus {
@@ -48,3 +71,31 @@ impl ConfirmationStatus {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_parse_every_confirmation_status() {
+        for status in [
+            ConfirmationStatus::Processed,
+            ConfirmationStatus::Confirmed,
+            ConfirmationStatus::Finalized,
+        ] {
+            assert_eq!(status.as_str().parse(), Ok(status));
+        }
+    }
+
+    #[test]
+    fn should_reject_unknown_confirmation_status() {
+        assert!("Finalized".parse::<ConfirmationStatus>().is_err());
+        assert!("".parse::<ConfirmationStatus>().is_err());
+    }
+
+    #[test]
+    fn should_order_confirmation_status_by_commitment() {
+        assert!(ConfirmationStatus::Processed < ConfirmationStatus::Confirmed);
+        assert!(ConfirmationStatus::Confirmed < ConfirmationStatus::Finalized);
+    }
+}
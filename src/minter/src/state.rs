@@ -1,19 +1,32 @@
-use crate::constants::DERIVATION_PATH;
+use crate::constants::{
+    DERIVATION_PATH, GET_LATEST_SOLANA_SIGNATURE, KNOWN_ECDSA_KEY_NAMES, MAX_DEPOSIT_ERRORS,
+    MAX_MINIMUM_WITHDRAWAL_TOKENS, MAX_TOKEN_DECIMALS, MAX_WITHDRAWAL_IDEMPOTENCY_KEYS,
+    MINTING_CIRCUIT_BREAKER_THRESHOLD, MINTING_PAUSE_COOLDOWN, MINT_GSOL, MINT_GSOL_RETRY_LIMIT,
+    SCRAPPING_SOLANA_SIGNATURES, SCRAPPING_SOLANA_SIGNATURE_RANGES,
+    SOLANA_SIGNATURE_RANGES_RETRY_LIMIT, SOLANA_SIGNATURE_RETRY_LIMIT, SOL_DECIMALS,
+    WITHDRAWAL_IDEMPOTENCY_WINDOW,
+};
+use crate::deposit::DepositError;
 use crate::events::{DepositEvent, SolanaSignature, SolanaSignatureRange, WithdrawalEvent};
-use crate::lifecycle::{SolanaRpcUrl, UpgradeArg};
+use crate::lifecycle::{RpcHeader, SolanaNetwork, SolanaRpcUrl, UpgradeArg};
+use crate::sol_rpc_client::{types::RpcMethod, LastRpcError};
+use crate::utils::Base58Utils;
+use crate::withdraw::{CouponHashAlgo, WithdrawError};
 
-use candid::Principal;
+use candid::{CandidType, Nat, Principal};
 use ic_cdk::api::management_canister::ecdsa::EcdsaPublicKeyResponse;
 use num_bigint::BigUint;
 use num_bigint::ToBigUint;
 use std::{
     cell::RefCell,
-    collections::{BTreeSet, HashMap, HashSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
 };
 use strum_macros::EnumIter;
 
 pub mod audit;
 pub mod event;
+#[cfg(test)]
+mod tests;
 
 thread_local! {
   pub static STATE: RefCell<Option<State>> = RefCell::default();
@@ -26,9 +39,15 @@ pub enum InvalidStateError {
     InvalidSolanaContractAddress(String),
     InvalidMinimumWithdrawalAmount(String),
     InvalidSolanaInitialSignature(String),
+    InvalidTokenDecimals(String),
+    InvalidMaxEventsPerTick(String),
+    InvalidLatestSignaturesBatchSize(String),
+    InvalidWithdrawalFee(String),
+    InvalidSolanaRpcUrl(String),
+    InvalidMaxDepositAmount(String),
 }
 
-#[derive(Debug, Hash, Copy, Clone, PartialEq, Eq, EnumIter)]
+#[derive(CandidType, Debug, Hash, Copy, Clone, PartialEq, Eq, EnumIter)]
 pub enum TaskType {
     GetLatestSignature,
     ScrapSignatureRanges,
@@ -36,11 +55,144 @@ pub enum TaskType {
     MintGSol,
 }
 
+#[derive(CandidType, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskMetrics {
+    // time (ns) the last completed run started at
+    pub last_run_timestamp: u64,
+    pub last_duration_ns: u64,
+    // instructions executed by the last completed run, across all its messages
+    pub last_instructions: u64,
+}
+
+// Burn triggered by a withdrawal carrying an idempotency key, keys are scoped to the caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IdempotentWithdrawal {
+    pub caller: Principal,
+    pub key: [u8; 32],
+    // a retry has to repeat the arguments of the original call
+    pub request: WithdrawalRequest,
+    pub burn_id: u64,
+    // time (ns) of the burn
+    pub timestamp: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WithdrawalRequest {
+    pub to: String,
+    pub amount: Nat,
+    pub from_subaccount: Option<[u8; 32]>,
+}
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
+pub struct RangeCursor {
+    // before signature of the next chunk to scan
+    pub cursor: String,
+    // signatures collected so far by the running scan
+    pub scanned_signatures: u64,
+}
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
+pub struct SignatureRangeProgress {
+    pub before_sol_sig: String,
+    // None when the range extends to the first transaction of the contract
+    pub until_sol_sig: Option<String>,
+    pub retries: u8,
+    // None until the range is being scanned
+    pub progress: Option<RangeCursor>,
+}
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
+pub struct TaskStatus {
+    pub task: TaskType,
+    pub active: bool,
+    pub metrics: Option<TaskMetrics>,
+}
+
+// A secp256k1 key is not an ed25519 Solana account, deposits are sent to the program instead.
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
+pub struct MinterSolanaAddress {
+    // Solana program receiving the deposits
+    pub deposit_address: String,
+    // 64 byte uncompressed public key without the 0x04 prefix, as returned by secp256k1_recover
+    // on Solana, base58 encoded
+    pub public_key_base58: String,
+}
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
+pub struct EncodedPublicKey {
+    // DER encoded SubjectPublicKeyInfo
+    pub der: serde_bytes::ByteBuf,
+    pub pem: String,
+}
+
+impl From<&ic_crypto_ecdsa_secp256k1::PublicKey> for EncodedPublicKey {
+    fn from(public_key: &ic_crypto_ecdsa_secp256k1::PublicKey) -> Self {
+        Self {
+            der: serde_bytes::ByteBuf::from(public_key.serialize_der()),
+            pem: public_key.serialize_pem(),
+        }
+    }
+}
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
+pub struct HealthStatus {
+    pub ecdsa_public_key_loaded: bool,
+    // time (ns) of the last successful getSignaturesForAddress call for new signatures
+    pub last_successful_scrape_timestamp: Option<u64>,
+    pub seconds_since_last_successful_scrape: Option<u64>,
+    pub exhausted_signature_ranges: u64,
+    pub exhausted_signatures: u64,
+    pub exhausted_deposits: u64,
+    // RPC calls are paused after the provider rate-limited us
+    pub rpc_paused: bool,
+    // minting is paused after repeated ledger call failures
+    pub minting_paused: bool,
+}
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
+pub struct ChainLag {
+    // slot reported by getSlot on the last scrape
+    pub latest_slot: Option<u64>,
+    // highest slot among the minted deposits still kept in state
+    pub last_minted_slot: Option<u64>,
+    pub lag_slots: Option<u64>,
+}
+
+// The RPC URL and headers are left out, they may carry provider API keys.
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
+pub struct MinterConfig {
+    pub solana_network: SolanaNetwork,
+    pub solana_contract_address: String,
+    pub additional_solana_contract_addresses: Vec<String>,
+    pub solana_initial_signature: String,
+    pub ledger_id: Principal,
+    pub ecdsa_key_name: String,
+    pub minimum_withdrawal_amount: Nat,
+    pub withdrawal_fee: Nat,
+    pub max_deposit_amount: Option<Nat>,
+    pub coupon_hash_algo: CouponHashAlgo,
+    pub token_decimals: u8,
+    pub max_events_per_tick: u64,
+    pub latest_signatures_batch_size: u8,
+    pub min_confirmation_slots: u64,
+    pub retention_slots: Option<u64>,
+    // intervals of the timer tasks, in seconds
+    pub get_latest_signature_interval_secs: u64,
+    pub scrap_signature_ranges_interval_secs: u64,
+    pub scrap_signatures_interval_secs: u64,
+    pub mint_gsol_interval_secs: u64,
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub struct State {
     // solana config
     pub solana_rpc_url: SolanaRpcUrl,
+    // tried in order when the primary provider fails
+    pub fallback_rpc_urls: Vec<SolanaRpcUrl>,
+    pub solana_rpc_headers: Vec<RpcHeader>,
     pub solana_contract_address: String,
+    // further contracts whose deposits are minted, scraped from their newest signature once added
+    pub additional_solana_contract_addresses: Vec<String>,
     pub solana_initial_signature: String,
 
     // icp config
@@ -49,28 +201,58 @@ pub struct State {
     pub ecdsa_public_key: Option<EcdsaPublicKeyResponse>,
     pub ledger_id: Principal,
     pub minimum_withdrawal_amount: BigUint,
+    // subtracted from the withdrawn amount, the coupon is signed for the rest
+    pub withdrawal_fee: BigUint,
+    // caps the amount of a single deposit, larger deposits are recorded as invalid
+    pub max_deposit_amount: Option<BigUint>,
+    // hash function applied to the coupon message before signing
+    pub coupon_hash_algo: CouponHashAlgo,
+    // principals besides the controllers allowed to call the operational update methods
+    pub authorized_callers: BTreeSet<Principal>,
+    // decimals of the gSOL ledger
+    pub token_decimals: u8,
 
     // scrapper config
+    // maximum number of signatures/deposits processed per timer tick
+    pub max_events_per_tick: u64,
+    // number of the newest signatures requested by get_latest_signature
+    pub latest_signatures_batch_size: u8,
+    // deposits are minted only once they are this many slots behind the chain tip
+    pub min_confirmation_slots: u64,
+    // minted and invalid events older than this many slots are pruned
+    pub retention_slots: Option<u64>,
     pub solana_last_known_signature: Option<String>,
+    // last known signature of each additional contract, by contract address
+    pub additional_last_known_signatures: BTreeMap<String, String>,
+
+    // history older than the initial signature is scraped at most once
+    pub backfill_from_genesis_requested: bool,
 
     pub solana_signature_ranges: HashMap<String, SolanaSignatureRange>,
     pub solana_signatures: HashMap<String, SolanaSignature>,
 
     // invalid transactions - cannot be parsed, does not hold deposit event, blocked user, etc.
-    pub invalid_events: HashMap<String, SolanaSignature>,
+    pub invalid_events: BTreeMap<String, SolanaSignature>,
     // valid transaction events
-    pub accepted_events: HashMap<String, DepositEvent>,
+    pub accepted_events: BTreeMap<String, DepositEvent>,
     // minted events
-    pub minted_events: HashMap<String, DepositEvent>,
+    pub minted_events: BTreeMap<String, DepositEvent>,
+    // accepted events that reached the minting retry limit
+    pub dead_letter_events: BTreeMap<String, DepositEvent>,
+    // minted and invalid events from slots before this one were pruned
+    pub pruned_before_slot: Option<u64>,
 
     // withdrawal with burned gSol
-    pub withdrawal_burned_events: HashMap<u64, WithdrawalEvent>,
+    pub withdrawal_burned_events: BTreeMap<u64, WithdrawalEvent>,
     // withdrawal with generated coupon
-    pub withdrawal_redeemed_events: HashMap<u64, WithdrawalEvent>,
+    pub withdrawal_redeemed_events: BTreeMap<u64, WithdrawalEvent>,
 
     // Withdrawal requests that are currently being processed
     pub withdrawing_principals: BTreeSet<Principal>,
 
+    // Burn ids for which a coupon is currently being generated
+    pub generating_coupons: BTreeSet<u64>,
+
     // Unique identifier for each deposit -> used during mint process for unique memo
     pub deposit_id_counter: u64,
 
@@ -78,40 +260,241 @@ pub struct State {
     // Burn execution is accepted as a start of the withdraw process.
     pub burn_id_counter: u64,
 
+    /// Time (ns) of the last successful scrape for new signatures since the last upgrade.
+    pub last_successful_scrape_timestamp: Option<u64>,
+
+    /// Highest Solana slot seen in RPC responses since the last upgrade.
+    pub last_observed_slot: Option<u64>,
+
+    /// Chain tip slot reported by the provider on the last scrape since the last upgrade.
+    pub latest_chain_slot: Option<u64>,
+
+    /// RPC calls are paused until this time (ns) after the provider rate-limited us.
+    pub rate_limited_until: Option<u64>,
+
+    /// Failed ledger calls in a row, resets on the first call reaching the ledger.
+    pub consecutive_ledger_failures: u32,
+
+    /// Minting is paused until this time (ns) after repeated ledger call failures.
+    pub minting_paused_until: Option<u64>,
+
     /// Number of HTTP outcalls since the last upgrade.
     pub http_request_counter: u64,
 
+    /// Last failed RPC call since the last upgrade.
+    pub last_rpc_error: Option<LastRpcError>,
+
+    /// Provider the last outcall was sent to since the last upgrade.
+    pub last_used_rpc_url: Option<SolanaRpcUrl>,
+
+    /// Largest response size (bytes) per item observed for each RPC method since the last upgrade.
+    pub observed_response_sizes: HashMap<RpcMethod, u64>,
+
     /// Locks preventing concurrent execution timer tasks
     pub active_tasks: HashSet<TaskType>,
+
+    /// Metrics of the last completed run of each timer task, not persisted across upgrades.
+    pub task_metrics: HashMap<TaskType, TaskMetrics>,
+
+    /// Scanning cursors of the ranges keyed like `solana_signature_ranges`, not persisted across upgrades.
+    pub range_cursors: HashMap<String, RangeCursor>,
+
+    /// Most recent deposit errors keyed by signature (or range), not persisted across upgrades.
+    pub deposit_errors: VecDeque<(String, DepositError)>,
+
+    /// Recent withdrawals made with an idempotency key, oldest first, not persisted across upgrades.
+    pub withdrawal_idempotency_keys: VecDeque<IdempotentWithdrawal>,
+}
+
+// The parts of the state rebuilt from the event log. The public key, the guards and the runtime-only
+// bookkeeping are left out, hash maps are sorted so the debug output is deterministic.
+#[derive(Debug, PartialEq)]
+struct CanonicalState<'a> {
+    solana_rpc_url: &'a SolanaRpcUrl,
+    fallback_rpc_urls: &'a [SolanaRpcUrl],
+    solana_rpc_headers: &'a [RpcHeader],
+    solana_contract_address: &'a str,
+    additional_solana_contract_addresses: &'a [String],
+    solana_initial_signature: &'a str,
+    ecdsa_key_name: &'a str,
+    ledger_id: &'a Principal,
+    minimum_withdrawal_amount: &'a BigUint,
+    withdrawal_fee: &'a BigUint,
+    max_deposit_amount: &'a Option<BigUint>,
+    coupon_hash_algo: &'a CouponHashAlgo,
+    authorized_callers: &'a BTreeSet<Principal>,
+    token_decimals: u8,
+    max_events_per_tick: u64,
+    latest_signatures_batch_size: u8,
+    min_confirmation_slots: u64,
+    retention_slots: Option<u64>,
+    solana_last_known_signature: &'a Option<String>,
+    additional_last_known_signatures: &'a BTreeMap<String, String>,
+    backfill_from_genesis_requested: bool,
+    solana_signature_ranges: BTreeMap<&'a String, &'a SolanaSignatureRange>,
+    solana_signatures: BTreeMap<&'a String, &'a SolanaSignature>,
+    invalid_events: &'a BTreeMap<String, SolanaSignature>,
+    accepted_events: &'a BTreeMap<String, DepositEvent>,
+    minted_events: &'a BTreeMap<String, DepositEvent>,
+    dead_letter_events: &'a BTreeMap<String, DepositEvent>,
+    pruned_before_slot: Option<u64>,
+    withdrawal_burned_events: &'a BTreeMap<u64, WithdrawalEvent>,
+    withdrawal_redeemed_events: &'a BTreeMap<u64, WithdrawalEvent>,
+    deposit_id_counter: u64,
+    burn_id_counter: u64,
 }
 
 impl State {
+    fn canonical(&self) -> CanonicalState<'_> {
+        CanonicalState {
+            solana_rpc_url: &self.solana_rpc_url,
+            fallback_rpc_urls: &self.fallback_rpc_urls,
+            solana_rpc_headers: &self.solana_rpc_headers,
+            solana_contract_address: &self.solana_contract_address,
+            additional_solana_contract_addresses: &self.additional_solana_contract_addresses,
+            solana_initial_signature: &self.solana_initial_signature,
+            ecdsa_key_name: &self.ecdsa_key_name,
+            ledger_id: &self.ledger_id,
+            minimum_withdrawal_amount: &self.minimum_withdrawal_amount,
+            withdrawal_fee: &self.withdrawal_fee,
+            max_deposit_amount: &self.max_deposit_amount,
+            coupon_hash_algo: &self.coupon_hash_algo,
+            authorized_callers: &self.authorized_callers,
+            token_decimals: self.token_decimals,
+            max_events_per_tick: self.max_events_per_tick,
+            latest_signatures_batch_size: self.latest_signatures_batch_size,
+            min_confirmation_slots: self.min_confirmation_slots,
+            retention_slots: self.retention_slots,
+            solana_last_known_signature: &self.solana_last_known_signature,
+            additional_last_known_signatures: &self.additional_last_known_signatures,
+            backfill_from_genesis_requested: self.backfill_from_genesis_requested,
+            solana_signature_ranges: self.solana_signature_ranges.iter().collect(),
+            solana_signatures: self.solana_signatures.iter().collect(),
+            invalid_events: &self.invalid_events,
+            accepted_events: &self.accepted_events,
+            minted_events: &self.minted_events,
+            dead_letter_events: &self.dead_letter_events,
+            pruned_before_slot: self.pruned_before_slot,
+            withdrawal_burned_events: &self.withdrawal_burned_events,
+            withdrawal_redeemed_events: &self.withdrawal_redeemed_events,
+            deposit_id_counter: self.deposit_id_counter,
+            burn_id_counter: self.burn_id_counter,
+        }
+    }
+
+    /// Whether both states hold the same data rebuilt from the event log, runtime-only fields aside.
+    pub fn is_equivalent_to(&self, other: &Self) -> bool {
+        self.canonical() == other.canonical()
+    }
+
+    /// Hex encoded SHA-256 of the parts compared by `is_equivalent_to`.
+    /// RPC header values are redacted in the debug output, so they do not change the hash.
+    pub fn canonical_hash(&self) -> String {
+        use sha2::{Digest, Sha256};
+
+        hex::encode(Sha256::digest(format!("{:?}", self.canonical()).as_bytes()))
+    }
+
     pub fn validate_config(&self) -> Result<(), InvalidStateError> {
         if self.ecdsa_key_name.trim().is_empty() {
             return Err(InvalidStateError::InvalidEcdsaKeyName(
                 "ecdsa_key_name cannot be blank".to_string(),
             ));
         }
+        // an unknown key is only noticed when the first signature traps, long after the deploy
+        if !KNOWN_ECDSA_KEY_NAMES.contains(&self.ecdsa_key_name.as_str()) {
+            return Err(InvalidStateError::InvalidEcdsaKeyName(format!(
+                "ecdsa_key_name {} is not one of {KNOWN_ECDSA_KEY_NAMES:?}",
+                self.ecdsa_key_name
+            )));
+        }
         if self.ledger_id == Principal::anonymous() {
             return Err(InvalidStateError::InvalidLedgerId(
                 "ledger_id cannot be the anonymous principal".to_string(),
             ));
         }
+        if self.solana_rpc_url.is_empty() {
+            return Err(InvalidStateError::InvalidSolanaRpcUrl(
+                "solana_rpc_url cannot be empty, at least one RPC provider is required".to_string(),
+            ));
+        }
+        if self.fallback_rpc_urls.iter().any(SolanaRpcUrl::is_empty) {
+            return Err(InvalidStateError::InvalidSolanaRpcUrl(
+                "fallback_rpc_urls cannot contain empty URLs".to_string(),
+            ));
+        }
         if self.solana_contract_address.trim().is_empty() {
             return Err(InvalidStateError::InvalidSolanaContractAddress(
                 "solana_contract_address cannot be empty".to_string(),
             ));
         }
+        for (index, address) in self.additional_solana_contract_addresses.iter().enumerate() {
+            if address.trim().is_empty() {
+                return Err(InvalidStateError::InvalidSolanaContractAddress(
+                    "additional_solana_contract_addresses cannot contain empty addresses"
+                        .to_string(),
+                ));
+            }
+            // a contract scraped twice would record every deposit twice
+            if self
+                .solana_contract_addresses()
+                .take(index + 1)
+                .any(|a| a == address)
+            {
+                return Err(InvalidStateError::InvalidSolanaContractAddress(format!(
+                    "solana contract address {address} is configured more than once"
+                )));
+            }
+        }
         if self.solana_initial_signature.trim().is_empty() {
             return Err(InvalidStateError::InvalidSolanaInitialSignature(
                 "solana_initial_signature cannot be empty".to_string(),
             ));
         }
+        // the initial signature is the until anchor of the first range, garbage is never matched
+        if let Err(err) = SolanaSignature::try_new(&self.solana_initial_signature, None) {
+            return Err(InvalidStateError::InvalidSolanaInitialSignature(format!(
+                "solana_initial_signature is not a valid signature: {err}"
+            )));
+        }
         if self.minimum_withdrawal_amount == BigUint::from(0u8) {
             return Err(InvalidStateError::InvalidMinimumWithdrawalAmount(
                 "minimum_withdrawal_amount must be positive".to_string(),
             ));
         }
+        let ceiling = BigUint::from(MAX_MINIMUM_WITHDRAWAL_TOKENS)
+            * BigUint::from(10u8).pow(self.token_decimals as u32);
+        if self.minimum_withdrawal_amount > ceiling {
+            return Err(InvalidStateError::InvalidMinimumWithdrawalAmount(format!(
+                "minimum_withdrawal_amount cannot be greater than {MAX_MINIMUM_WITHDRAWAL_TOKENS} tokens ({ceiling})"
+            )));
+        }
+        // every valid withdrawal has to leave something after the fee
+        if self.withdrawal_fee >= self.minimum_withdrawal_amount {
+            return Err(InvalidStateError::InvalidWithdrawalFee(
+                "withdrawal_fee must be lower than minimum_withdrawal_amount".to_string(),
+            ));
+        }
+        if self.max_deposit_amount == Some(BigUint::from(0u8)) {
+            return Err(InvalidStateError::InvalidMaxDepositAmount(
+                "max_deposit_amount must be positive".to_string(),
+            ));
+        }
+        if self.token_decimals > MAX_TOKEN_DECIMALS {
+            return Err(InvalidStateError::InvalidTokenDecimals(format!(
+                "token_decimals cannot be greater than {MAX_TOKEN_DECIMALS}"
+            )));
+        }
+        if self.max_events_per_tick == 0 {
+            return Err(InvalidStateError::InvalidMaxEventsPerTick(
+                "max_events_per_tick must be positive".to_string(),
+            ));
+        }
+        if self.latest_signatures_batch_size == 0 {
+            return Err(InvalidStateError::InvalidLatestSignaturesBatchSize(
+                "latest_signatures_batch_size must be positive".to_string(),
+            ));
+        }
         Ok(())
     }
 
@@ -122,13 +505,35 @@ impl State {
             solana_initial_signature,
             ecdsa_key_name,
             minimum_withdrawal_amount,
+            ledger_id,
+            max_events_per_tick,
+            solana_rpc_headers,
+            min_confirmation_slots,
+            retention_slots,
+            reset_all_retries,
+            latest_signatures_batch_size,
+            withdrawal_fee,
+            coupon_hash_algo,
+            authorized_callers,
+            additional_solana_contract_addresses,
+            max_deposit_amount,
+            fallback_rpc_urls,
         } = upgrade_args;
         if let Some(url) = solana_rpc_url {
             self.solana_rpc_url = url;
         }
+        if let Some(urls) = fallback_rpc_urls {
+            self.fallback_rpc_urls = urls;
+        }
+        if let Some(headers) = solana_rpc_headers {
+            self.solana_rpc_headers = headers;
+        }
         if let Some(address) = solana_contract_address {
             self.solana_contract_address = address;
         }
+        if let Some(addresses) = additional_solana_contract_addresses {
+            self.additional_solana_contract_addresses = addresses;
+        }
         if let Some(signature) = solana_initial_signature {
             self.solana_initial_signature = signature;
         }
@@ -145,6 +550,36 @@ impl State {
                     ))?;
             self.minimum_withdrawal_amount = amount;
         }
+        if let Some(fee) = withdrawal_fee {
+            self.withdrawal_fee = fee.0;
+        }
+        if let Some(amount) = max_deposit_amount {
+            self.max_deposit_amount = Some(amount.0);
+        }
+        if let Some(hash_algo) = coupon_hash_algo {
+            self.coupon_hash_algo = hash_algo;
+        }
+        if let Some(callers) = authorized_callers {
+            self.authorized_callers = callers.into_iter().collect();
+        }
+        if let Some(ledger_id) = ledger_id {
+            self.ledger_id = ledger_id;
+        }
+        if let Some(max_events_per_tick) = max_events_per_tick {
+            self.max_events_per_tick = max_events_per_tick;
+        }
+        if let Some(batch_size) = latest_signatures_batch_size {
+            self.latest_signatures_batch_size = batch_size;
+        }
+        if let Some(min_confirmation_slots) = min_confirmation_slots {
+            self.min_confirmation_slots = min_confirmation_slots;
+        }
+        if let Some(retention_slots) = retention_slots {
+            self.retention_slots = Some(retention_slots);
+        }
+        if reset_all_retries == Some(true) {
+            self.reset_all_retries();
+        }
         self.validate_config()
     }
 
@@ -173,10 +608,74 @@ impl State {
         }
     }
 
+    pub fn minter_solana_address(&self) -> MinterSolanaAddress {
+        let uncompressed = hex::decode(self.uncompressed_public_key())
+            .expect("BUG: uncompressed public key is hex encoded");
+
+        MinterSolanaAddress {
+            deposit_address: self.solana_contract_address.clone(),
+            public_key_base58: Base58Utils::encode(&uncompressed[1..]),
+        }
+    }
+
+    // converts an amount of lamports into the ledger's decimal base
+    pub fn lamports_to_gsol(&self, lamports: &Nat) -> Nat {
+        use std::cmp::Ordering;
+
+        let ten = BigUint::from(10u8);
+        match self.token_decimals.cmp(&SOL_DECIMALS) {
+            Ordering::Equal => lamports.clone(),
+            Ordering::Greater => {
+                Nat(&lamports.0 * ten.pow((self.token_decimals - SOL_DECIMALS) as u32))
+            }
+            Ordering::Less => {
+                Nat(&lamports.0 / ten.pow((SOL_DECIMALS - self.token_decimals) as u32))
+            }
+        }
+    }
+
+    // converts an amount in the ledger's decimal base back into lamports,
+    // None if it is not a whole number of lamports
+    pub fn gsol_to_lamports(&self, amount: &Nat) -> Option<Nat> {
+        use std::cmp::Ordering;
+
+        let ten = BigUint::from(10u8);
+        match self.token_decimals.cmp(&SOL_DECIMALS) {
+            Ordering::Equal => Some(amount.clone()),
+            Ordering::Greater => {
+                let granularity = self.lamport_granularity();
+                (&amount.0 % &granularity == BigUint::from(0u8))
+                    .then(|| Nat(&amount.0 / granularity))
+            }
+            Ordering::Less => Some(Nat(
+                &amount.0 * ten.pow((SOL_DECIMALS - self.token_decimals) as u32)
+            )),
+        }
+    }
+
+    // smallest amount in the ledger's decimal base that converts into a whole number of lamports
+    pub fn lamport_granularity(&self) -> BigUint {
+        BigUint::from(10u8).pow(self.token_decimals.saturating_sub(SOL_DECIMALS) as u32)
+    }
+
     pub fn solana_rpc_url(&self) -> SolanaRpcUrl {
         self.solana_rpc_url.clone()
     }
 
+    pub fn fallback_rpc_urls(&self) -> Vec<SolanaRpcUrl> {
+        self.fallback_rpc_urls.clone()
+    }
+
+    // The primary contract followed by the additional ones.
+    pub fn solana_contract_addresses(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.solana_contract_address)
+            .chain(self.additional_solana_contract_addresses.iter())
+    }
+
+    pub fn solana_rpc_headers(&self) -> Vec<RpcHeader> {
+        self.solana_rpc_headers.clone()
+    }
+
     // STATE TRASNFORMATIONS
     pub fn record_solana_last_known_signature(&mut self, sig: &String) {
         self.solana_last_known_signature = Some(sig.to_string());
@@ -190,7 +689,7 @@ impl State {
     }
 
     pub fn record_solana_signature_range(&mut self, range: SolanaSignatureRange) {
-        let key = range_key(&range.before_sol_sig, &range.until_sol_sig);
+        let key = range_key(&range.before_sol_sig, range.until_as_str());
 
         match self.solana_signature_ranges.contains_key(&key) {
             true => {
@@ -202,12 +701,27 @@ impl State {
         }
     }
 
+    pub fn record_backfill_from_genesis(&mut self) {
+        assert!(
+            !self.backfill_from_genesis_requested,
+            "Attempted to backfill from genesis twice."
+        );
+
+        self.backfill_from_genesis_requested = true;
+        self.record_solana_signature_range(SolanaSignatureRange::new(
+            self.solana_initial_signature.to_string(),
+            None,
+        ));
+    }
+
     pub fn retry_solana_signature_range(
         &mut self,
         old_range: SolanaSignatureRange,
         new_range: Option<SolanaSignatureRange>,
     ) {
-        let old_key = range_key(&old_range.before_sol_sig, &old_range.until_sol_sig);
+        let old_key = range_key(&old_range.before_sol_sig, old_range.until_as_str());
+
+        self.range_cursors.remove(&old_key);
 
         match self.solana_signature_ranges.remove(&old_key) {
             Some(mut old_range) => {
@@ -224,17 +738,96 @@ impl State {
                     }
                 }
             }
-            None => panic!("Attempted to re-record NON existing range: {old_key} ."),
+            // overlapping scans can complete the range first, it needs no retry then
+            None => ic_canister_log::log!(
+                crate::logs::INFO,
+                "\nAttempted to re-record NON existing range: {old_key}, ignoring"
+            ),
         }
     }
 
+    pub fn is_range_retry_limit_reached(&self, range: &SolanaSignatureRange) -> bool {
+        let key = range_key(&range.before_sol_sig, range.until_as_str());
+        self.solana_signature_ranges.get(&key).is_some_and(|r| {
+            r.retry
+                .is_retry_limit_reached(SOLANA_SIGNATURE_RANGES_RETRY_LIMIT)
+        })
+    }
+
+    pub fn is_signature_retry_limit_reached(&self, key: &str) -> bool {
+        self.solana_signatures
+            .get(key)
+            .is_some_and(|s| s.retry.is_retry_limit_reached(SOLANA_SIGNATURE_RETRY_LIMIT))
+    }
+
     pub fn remove_solana_signature_range(&mut self, range: &SolanaSignatureRange) {
-        let key = range_key(&range.before_sol_sig, &range.until_sol_sig);
+        let key = range_key(&range.before_sol_sig, range.until_as_str());
+        self.range_cursors.remove(&key);
+
+        // overlapping scans can complete the same range twice
+        if self.solana_signature_ranges.remove(&key).is_none() {
+            ic_canister_log::log!(
+                crate::logs::INFO,
+                "\nAttempted to remove NON existing range: {key}, ignoring"
+            );
+        }
+    }
 
-        match self.solana_signature_ranges.remove(&key) {
-            Some(_) => {}
-            None => panic!("Attempted to remove NON existing range: {key} ."),
-        };
+    // Pending range starting where the given range ends, so both can be scanned as one.
+    // Ranges are left alone while being scanned or once retried.
+    pub fn mergeable_signature_range(
+        &self,
+        range: &SolanaSignatureRange,
+    ) -> Option<SolanaSignatureRange> {
+        if self.active_tasks.contains(&TaskType::ScrapSignatureRanges) {
+            return None;
+        }
+        let until = range.until_sol_sig.as_ref()?;
+
+        self.solana_signature_ranges
+            .values()
+            .find(|r| {
+                r.before_sol_sig == *until
+                    && r.contract_address == range.contract_address
+                    && r.retry.get_retries() == 0
+            })
+            .cloned()
+    }
+
+    pub fn record_range_cursor(
+        &mut self,
+        range: &SolanaSignatureRange,
+        cursor: &str,
+        scanned_signatures: u64,
+    ) {
+        self.range_cursors.insert(
+            range_key(&range.before_sol_sig, range.until_as_str()),
+            RangeCursor {
+                cursor: cursor.to_string(),
+                scanned_signatures,
+            },
+        );
+    }
+
+    pub fn signature_range_progress(&self) -> Vec<SignatureRangeProgress> {
+        self.solana_signature_ranges
+            .iter()
+            .map(|(key, range)| SignatureRangeProgress {
+                before_sol_sig: range.before_sol_sig.clone(),
+                until_sol_sig: range.until_sol_sig.clone(),
+                retries: range.retry.get_retries(),
+                progress: self.range_cursors.get(key).cloned(),
+            })
+            .collect()
+    }
+
+    // Whether the signature is already queued for scraping or was handled (pruned events excluded).
+    pub fn is_known_signature(&self, sig: &str) -> bool {
+        self.solana_signatures.contains_key(sig)
+            || self.invalid_events.contains_key(sig)
+            || self.accepted_events.contains_key(sig)
+            || self.minted_events.contains_key(sig)
+            || self.dead_letter_events.contains_key(sig)
     }
 
     pub fn record_or_retry_solana_signature(&mut self, sig: SolanaSignature) {
@@ -262,10 +855,14 @@ impl State {
             None => panic!("Attempted to remove NON existing solana signature {key} ."),
         };
 
-        assert!(
-            !self.invalid_events.contains_key(key),
-            "Attempted to record existing invalid event: {key} ."
-        );
+        // A signature can be scraped again after it was marked invalid (e.g. overlapping ranges).
+        // Panicking here would also break event replay, so the latest record simply wins.
+        if self.invalid_events.contains_key(key) {
+            ic_canister_log::log!(
+                crate::logs::INFO,
+                "\nRecording existing invalid event again: {key}"
+            );
+        }
 
         sig.retry.reset_retries();
         self.invalid_events.insert(key.to_string(), sig);
@@ -301,11 +898,17 @@ impl State {
     pub fn record_minted_event(&mut self, mut deposit: DepositEvent) {
         let key = &deposit.sol_sig;
 
-        _ = match self.accepted_events.remove(key) {
+        let accepted = match self.accepted_events.remove(key) {
             Some(event) => event,
             None => panic!("Attempted to remove NON existing accepted event: {key} ."),
         };
 
+        // a tampered event log must not mint a different amount than was deposited
+        assert_eq!(
+            accepted.amount, deposit.amount,
+            "Minted amount differs from accepted deposit amount: {key}.",
+        );
+
         assert!(
             !self.minted_events.contains_key(key),
             "Attempted to record existing minted event: {key}.",
@@ -315,6 +918,36 @@ impl State {
         _ = self.minted_events.insert(key.to_string(), deposit);
     }
 
+    pub fn record_dead_letter_event(&mut self, deposit: DepositEvent) {
+        let key = &deposit.sol_sig;
+
+        _ = match self.accepted_events.remove(key) {
+            Some(event) => event,
+            None => panic!("Attempted to remove NON existing accepted event: {key} ."),
+        };
+
+        _ = self.dead_letter_events.insert(key.to_string(), deposit);
+    }
+
+    pub fn retry_dead_letter_event(&mut self, key: &str) {
+        let mut deposit = match self.dead_letter_events.remove(key) {
+            Some(event) => event,
+            None => panic!("Attempted to remove NON existing dead letter event: {key} ."),
+        };
+
+        deposit.retry.reset_retries();
+        _ = self.accepted_events.insert(key.to_string(), deposit);
+    }
+
+    pub fn record_redemption_signature(&mut self, burn_id: u64, sol_sig: &str) {
+        match self.withdrawal_redeemed_events.get_mut(&burn_id) {
+            Some(event) => event.update_redemption_sol_sig(sol_sig.to_string()),
+            None => {
+                panic!("Attempted to record redemption of NON redeemed withdrawal: {burn_id} .")
+            }
+        }
+    }
+
     pub fn record_or_retry_withdrawal_burned_event(&mut self, withdrawal: WithdrawalEvent) {
         let key = withdrawal.get_burn_id();
 
@@ -339,6 +972,11 @@ impl State {
 
         match self.withdrawal_burned_events.remove(&key) {
             Some(_) => {
+                assert!(
+                    !self.withdrawal_redeemed_events.contains_key(&key),
+                    "Attempted to record existing withdrawal redeemed event: {key}."
+                );
+
                 withdrawal.retry.reset_retries();
                 self.withdrawal_redeemed_events.insert(key, withdrawal);
             }
@@ -346,6 +984,271 @@ impl State {
         }
     }
 
+    pub fn reset_all_retries(&mut self) {
+        self.solana_signature_ranges
+            .values_mut()
+            .for_each(|r| r.retry.reset_retries());
+        self.solana_signatures
+            .values_mut()
+            .for_each(|s| s.retry.reset_retries());
+        self.accepted_events
+            .values_mut()
+            .for_each(|e| e.retry.reset_retries());
+
+        let dead_letter_keys: Vec<String> = self.dead_letter_events.keys().cloned().collect();
+        for key in dead_letter_keys {
+            self.retry_dead_letter_event(&key);
+        }
+    }
+
+    pub fn has_prunable_events(&self, before_slot: u64) -> bool {
+        let is_old = |slot: Option<u64>| slot.is_some_and(|slot| slot < before_slot);
+
+        self.minted_events.values().any(|e| is_old(e.slot))
+            || self.invalid_events.values().any(|s| is_old(s.slot))
+    }
+
+    // Drops minted and invalid events from slots before `before_slot`, events without a slot are kept.
+    pub fn prune_events(&mut self, before_slot: u64) {
+        let is_old = |slot: Option<u64>| slot.is_some_and(|slot| slot < before_slot);
+
+        self.minted_events.retain(|_, e| !is_old(e.slot));
+        self.invalid_events.retain(|_, s| !is_old(s.slot));
+        self.pruned_before_slot = Some(
+            self.pruned_before_slot
+                .map_or(before_slot, |s| s.max(before_slot)),
+        );
+    }
+
+    pub fn is_pruned_slot(&self, slot: u64) -> bool {
+        self.pruned_before_slot
+            .is_some_and(|before_slot| slot < before_slot)
+    }
+
+    pub fn record_deposit_error(&mut self, key: String, err: DepositError) {
+        if self.deposit_errors.len() >= MAX_DEPOSIT_ERRORS {
+            self.deposit_errors.pop_front();
+        }
+        self.deposit_errors.push_back((key, err));
+    }
+
+    // Burn of the earlier call with the same key, the key may not be reused for another request.
+    pub fn idempotent_burn_id(
+        &self,
+        caller: &Principal,
+        key: &[u8; 32],
+        request: &WithdrawalRequest,
+        now: u64,
+    ) -> Result<Option<u64>, WithdrawError> {
+        let window = WITHDRAWAL_IDEMPOTENCY_WINDOW.as_nanos() as u64;
+        match self
+            .withdrawal_idempotency_keys
+            .iter()
+            .find(|w| w.caller == *caller && w.key == *key)
+            .filter(|w| now.saturating_sub(w.timestamp) < window)
+        {
+            Some(w) if w.request != *request => {
+                Err(WithdrawError::IdempotencyKeyMismatch { burn_id: w.burn_id })
+            }
+            Some(w) => Ok(Some(w.burn_id)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn record_idempotency_key(
+        &mut self,
+        caller: Principal,
+        key: [u8; 32],
+        request: WithdrawalRequest,
+        burn_id: u64,
+        now: u64,
+    ) {
+        let window = WITHDRAWAL_IDEMPOTENCY_WINDOW.as_nanos() as u64;
+        while self.withdrawal_idempotency_keys.front().is_some_and(|w| {
+            now.saturating_sub(w.timestamp) >= window
+                || self.withdrawal_idempotency_keys.len() >= MAX_WITHDRAWAL_IDEMPOTENCY_KEYS
+        }) {
+            self.withdrawal_idempotency_keys.pop_front();
+        }
+        self.withdrawal_idempotency_keys
+            .push_back(IdempotentWithdrawal {
+                caller,
+                key,
+                request,
+                burn_id,
+                timestamp: now,
+            });
+    }
+
+    // Completes the run of the task started at `started_at`.
+    pub fn record_task_run(
+        &mut self,
+        task: TaskType,
+        started_at: u64,
+        now: u64,
+        instructions: u64,
+    ) {
+        self.active_tasks.remove(&task);
+        self.task_metrics.insert(
+            task,
+            TaskMetrics {
+                last_run_timestamp: started_at,
+                last_duration_ns: now.saturating_sub(started_at),
+                last_instructions: instructions,
+            },
+        );
+    }
+
+    pub fn task_statuses(&self) -> Vec<TaskStatus> {
+        use strum::IntoEnumIterator;
+
+        TaskType::iter()
+            .map(|task| TaskStatus {
+                task,
+                active: self.active_tasks.contains(&task),
+                metrics: self.task_metrics.get(&task).copied(),
+            })
+            .collect()
+    }
+
+    pub fn config(&self) -> MinterConfig {
+        MinterConfig {
+            solana_network: self.solana_rpc_url.network(),
+            solana_contract_address: self.solana_contract_address.clone(),
+            additional_solana_contract_addresses: self.additional_solana_contract_addresses.clone(),
+            solana_initial_signature: self.solana_initial_signature.clone(),
+            ledger_id: self.ledger_id,
+            ecdsa_key_name: self.ecdsa_key_name.clone(),
+            minimum_withdrawal_amount: Nat::from(self.minimum_withdrawal_amount.clone()),
+            withdrawal_fee: Nat::from(self.withdrawal_fee.clone()),
+            max_deposit_amount: self.max_deposit_amount.clone().map(Nat::from),
+            coupon_hash_algo: self.coupon_hash_algo,
+            token_decimals: self.token_decimals,
+            max_events_per_tick: self.max_events_per_tick,
+            latest_signatures_batch_size: self.latest_signatures_batch_size,
+            min_confirmation_slots: self.min_confirmation_slots,
+            retention_slots: self.retention_slots,
+            get_latest_signature_interval_secs: GET_LATEST_SOLANA_SIGNATURE.as_secs(),
+            scrap_signature_ranges_interval_secs: SCRAPPING_SOLANA_SIGNATURE_RANGES.as_secs(),
+            scrap_signatures_interval_secs: SCRAPPING_SOLANA_SIGNATURES.as_secs(),
+            mint_gsol_interval_secs: MINT_GSOL.as_secs(),
+        }
+    }
+
+    pub fn health(&self, now: u64) -> HealthStatus {
+        HealthStatus {
+            ecdsa_public_key_loaded: self.ecdsa_public_key.is_some(),
+            last_successful_scrape_timestamp: self.last_successful_scrape_timestamp,
+            seconds_since_last_successful_scrape: self
+                .last_successful_scrape_timestamp
+                .map(|t| now.saturating_sub(t) / 1_000_000_000),
+            exhausted_signature_ranges: self
+                .solana_signature_ranges
+                .values()
+                .filter(|r| {
+                    r.retry
+                        .is_retry_limit_reached(SOLANA_SIGNATURE_RANGES_RETRY_LIMIT)
+                })
+                .count() as u64,
+            exhausted_signatures: self
+                .solana_signatures
+                .values()
+                .filter(|s| s.retry.is_retry_limit_reached(SOLANA_SIGNATURE_RETRY_LIMIT))
+                .count() as u64,
+            exhausted_deposits: self
+                .accepted_events
+                .values()
+                .filter(|e| e.retry.is_retry_limit_reached(MINT_GSOL_RETRY_LIMIT))
+                .count() as u64
+                + self.dead_letter_events.len() as u64,
+            rpc_paused: self.is_rate_limited(now),
+            minting_paused: self.is_minting_paused(now),
+        }
+    }
+
+    // Withdrawals of amounts below the ledger fee would fail when burning.
+    pub fn is_minimum_withdrawal_below_ledger_fee(&self, ledger_fee: &Nat) -> bool {
+        self.minimum_withdrawal_amount < ledger_fee.0
+    }
+
+    // Privileged endpoints (force minting, recording redemptions) are open to controllers and
+    // the configured authorized callers only.
+    pub fn is_authorized_caller(&self, caller: &Principal, is_controller: bool) -> bool {
+        is_controller || self.authorized_callers.contains(caller)
+    }
+
+    pub fn is_minting_paused(&self, now: u64) -> bool {
+        self.minting_paused_until.is_some_and(|until| now < until)
+    }
+
+    // Counts consecutive failed ledger calls and pauses minting once the threshold is reached.
+    // Returns true if this call tripped the breaker.
+    pub fn record_ledger_call(&mut self, succeeded: bool, now: u64) -> bool {
+        if succeeded {
+            self.consecutive_ledger_failures = 0;
+            return false;
+        }
+
+        self.consecutive_ledger_failures += 1;
+        if self.consecutive_ledger_failures < MINTING_CIRCUIT_BREAKER_THRESHOLD {
+            return false;
+        }
+
+        self.consecutive_ledger_failures = 0;
+        self.minting_paused_until = Some(now + MINTING_PAUSE_COOLDOWN.as_nanos() as u64);
+        true
+    }
+
+    pub fn resume_minting(&mut self) {
+        self.consecutive_ledger_failures = 0;
+        self.minting_paused_until = None;
+    }
+
+    pub fn is_rate_limited(&self, now: u64) -> bool {
+        self.rate_limited_until.is_some_and(|until| now < until)
+    }
+
+    // A deposit is mintable once its slot is deep enough behind the chain tip. The tip reported by
+    // getSlot advances every scrape even without new contract transactions, it is rounded down, so
+    // it never overstates the depth. Deposits recorded before slots were tracked are always mintable.
+    pub fn is_deposit_confirmed(&self, deposit: &DepositEvent) -> bool {
+        match (deposit.slot, self.min_confirmation_slots) {
+            (None, _) | (_, 0) => true,
+            (Some(slot), depth) => self
+                .latest_chain_slot
+                .max(self.last_observed_slot)
+                .is_some_and(|latest| latest >= slot.saturating_add(depth)),
+        }
+    }
+
+    pub fn record_observed_slot(&mut self, slot: u64) {
+        self.last_observed_slot = Some(self.last_observed_slot.map_or(slot, |s| s.max(slot)));
+    }
+
+    pub fn chain_lag(&self) -> ChainLag {
+        let last_minted_slot = self.minted_events.values().filter_map(|e| e.slot).max();
+        ChainLag {
+            latest_slot: self.latest_chain_slot,
+            last_minted_slot,
+            lag_slots: self
+                .latest_chain_slot
+                .zip(last_minted_slot)
+                .map(|(latest, minted)| latest.saturating_sub(minted)),
+        }
+    }
+
+    pub fn observed_response_size(&self, method: RpcMethod) -> u64 {
+        self.observed_response_sizes
+            .get(&method)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn record_response_size(&mut self, method: RpcMethod, item_size: u64) {
+        let observed = self.observed_response_sizes.entry(method).or_default();
+        *observed = (*observed).max(item_size);
+    }
+
     pub fn next_request_id(&mut self) -> u64 {
         let current_request_id = self.http_request_counter;
         // overflow is not an issue here because we only use `next_request_id` to correlate
@@ -354,15 +1257,23 @@ impl State {
         current_request_id
     }
 
+    // Unlike the request counter, a wrapped deposit or burn id would collide with an existing one
+    // and overwrite its event, so running out of ids is fatal.
     pub fn next_deposit_id(&mut self) -> u64 {
         let current_deposit_id = self.deposit_id_counter;
-        self.deposit_id_counter = self.deposit_id_counter.wrapping_add(1);
+        self.deposit_id_counter = self
+            .deposit_id_counter
+            .checked_add(1)
+            .expect("BUG: deposit id counter overflow");
         current_deposit_id
     }
 
     pub fn next_burn_id(&mut self) -> u64 {
         let current_withdrawal_id = self.burn_id_counter;
-        self.burn_id_counter = self.burn_id_counter.wrapping_add(1);
+        self.burn_id_counter = self
+            .burn_id_counter
+            .checked_add(1)
+            .expect("BUG: burn id counter overflow");
         current_withdrawal_id
     }
 
@@ -381,11 +1292,18 @@ impl std::fmt::Display for State {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // Format Solana config
         writeln!(f, "Solana RPC URL: {:?}", self.solana_rpc_url)?;
+        writeln!(f, "Fallback RPC URLs: {:?}", self.fallback_rpc_urls)?;
+        writeln!(f, "Solana RPC Headers: {:?}", self.solana_rpc_headers)?;
         writeln!(
             f,
             "Solana Contract Address: {}",
             self.solana_contract_address
         )?;
+        writeln!(
+            f,
+            "Additional Solana Contract Addresses: {:?}",
+            self.additional_solana_contract_addresses
+        )?;
         writeln!(
             f,
             "Solana Initial Signature: {}",
@@ -403,8 +1321,28 @@ impl std::fmt::Display for State {
             "Minimum Withdrawal Amount: {}",
             self.minimum_withdrawal_amount
         )?;
+        writeln!(f, "Withdrawal Fee: {}", self.withdrawal_fee)?;
+        if let Some(amount) = &self.max_deposit_amount {
+            writeln!(f, "Max Deposit Amount: {amount}")?;
+        }
+        writeln!(f, "Coupon Hash Algo: {:?}", self.coupon_hash_algo)?;
+        writeln!(f, "Authorized Callers: {:?}", self.authorized_callers)?;
+        writeln!(f, "Token Decimals: {}", self.token_decimals)?;
 
         // Format Scrapper config
+        writeln!(f, "Max Events Per Tick: {}", self.max_events_per_tick)?;
+        writeln!(
+            f,
+            "Latest Signatures Batch Size: {}",
+            self.latest_signatures_batch_size
+        )?;
+        writeln!(f, "Min Confirmation Slots: {}", self.min_confirmation_slots)?;
+        if let Some(retention_slots) = self.retention_slots {
+            writeln!(f, "Retention Slots: {retention_slots}")?;
+        }
+        if let Some(slot) = self.pruned_before_slot {
+            writeln!(f, "Pruned Before Slot: {slot}")?;
+        }
         if let Some(solana_last_known_signature) = &self.solana_last_known_signature {
             writeln!(
                 f,
@@ -412,6 +1350,18 @@ impl std::fmt::Display for State {
                 solana_last_known_signature
             )?;
         }
+        if !self.additional_last_known_signatures.is_empty() {
+            writeln!(
+                f,
+                "Additional Last Known Signatures: {:?}",
+                self.additional_last_known_signatures
+            )?;
+        }
+        writeln!(
+            f,
+            "Backfill From Genesis Requested: {}",
+            self.backfill_from_genesis_requested
+        )?;
         writeln!(
             f,
             "Solana Signature Ranges: {:?}",
@@ -423,6 +1373,7 @@ impl std::fmt::Display for State {
         writeln!(f, "Invalid Events: {:?}", self.invalid_events)?;
         writeln!(f, "Accepted Events: {:?}", self.accepted_events)?;
         writeln!(f, "Minted Events: {:?}", self.minted_events)?;
+        writeln!(f, "Dead Letter Events: {:?}", self.dead_letter_events)?;
 
         // Format withdrawal events
         writeln!(
@@ -442,11 +1393,35 @@ impl std::fmt::Display for State {
             "Withdrawing Principals: {:?}",
             self.withdrawing_principals
         )?;
+        writeln!(f, "Generating Coupons: {:?}", self.generating_coupons)?;
 
         // Format counters
         writeln!(f, "Deposit ID Counter: {}", self.deposit_id_counter)?;
         writeln!(f, "Burn ID Counter: {}", self.burn_id_counter)?;
+        if let Some(slot) = self.last_observed_slot {
+            writeln!(f, "Last Observed Slot: {slot}")?;
+        }
+        if let Some(slot) = self.latest_chain_slot {
+            writeln!(f, "Latest Chain Slot: {slot}")?;
+        }
+        if let Some(until) = self.rate_limited_until {
+            writeln!(f, "Rate Limited Until: {until}")?;
+        }
+        if let Some(until) = self.minting_paused_until {
+            writeln!(f, "Minting Paused Until: {until}")?;
+        }
         writeln!(f, "HTTP Request Counter: {}", self.http_request_counter)?;
+        if let Some(error) = &self.last_rpc_error {
+            writeln!(f, "Last RPC Error: {:?}", error)?;
+        }
+        if let Some(url) = &self.last_used_rpc_url {
+            writeln!(f, "Last Used RPC URL: {:?}", url)?;
+        }
+        writeln!(
+            f,
+            "Observed Response Sizes: {:?}",
+            self.observed_response_sizes
+        )?;
 
         // Format active tasks
         writeln!(f, "Active Tasks: {:?}", self.active_tasks)
@@ -471,6 +1446,27 @@ where
     })
 }
 
+// The coupon flow re-encodes the stored compressed key with libsecp256k1,
+// both encodings have to describe the same point.
+fn validate_ecdsa_public_key(
+    response: &EcdsaPublicKeyResponse,
+    public_key: &ic_crypto_ecdsa_secp256k1::PublicKey,
+) {
+    use libsecp256k1::{PublicKey, PublicKeyFormat};
+
+    let parsed = PublicKey::parse_slice(&response.public_key, Some(PublicKeyFormat::Compressed))
+        .unwrap_or_else(|e| {
+            ic_cdk::trap(&format!(
+                "minter's public key is not a compressed SEC1 point: {:?}",
+                e
+            ))
+        });
+
+    if parsed.serialize().to_vec() != public_key.serialize_sec1(false) {
+        ic_cdk::trap("minter's compressed and uncompressed public keys do not match");
+    }
+}
+
 pub async fn lazy_call_ecdsa_public_key() -> ic_crypto_ecdsa_secp256k1::PublicKey {
     use ic_cdk::api::management_canister::ecdsa::{
         ecdsa_public_key, EcdsaCurve, EcdsaKeyId, EcdsaPublicKeyArgument,
@@ -509,11 +1505,15 @@ pub async fn lazy_call_ecdsa_public_key() -> ic_crypto_ecdsa_secp256k1::PublicKe
         ))
     });
 
+    // Validate the key once before storing it, so the coupon flow never hits a malformed key.
+    let public_key = to_public_key(&response);
+    validate_ecdsa_public_key(&response, &public_key);
+
     mutate_state(|s| s.ecdsa_public_key = Some(response.clone()));
 
-    to_public_key(&response)
+    public_key
 }
 
-fn range_key(start: &String, end: &String) -> String {
+fn range_key(start: &str, end: &str) -> String {
     return format!("{}-{}", start, end);
 }
@@ -67,6 +67,28 @@ pub fn apply_state_transition(state: &mut State, payload: &EventType) {
         EventType::WithdrawalRedeemedEvent { event_source } => {
             state.record_withdrawal_redeemed_event(event_source.clone());
         }
+        EventType::PrunedEvents { before_slot } => {
+            state.prune_events(*before_slot);
+        }
+        EventType::BackfillFromGenesis => {
+            state.record_backfill_from_genesis();
+        }
+        EventType::DeadLetterEvent { event_source } => {
+            state.record_dead_letter_event(event_source.clone());
+        }
+        EventType::RetryDeadLetterEvent { sol_sig } => {
+            state.retry_dead_letter_event(sol_sig);
+        }
+        EventType::RedemptionSignature { burn_id, sol_sig } => {
+            state.record_redemption_signature(*burn_id, sol_sig);
+        }
+        // only a durable record for operators, the retries are already counted
+        EventType::RetryLimitReached { .. } => {}
+        EventType::LastKnownContractSignature { address, signature } => {
+            state
+                .additional_last_known_signatures
+                .insert(address.to_string(), signature.to_string());
+        }
     }
 }
 
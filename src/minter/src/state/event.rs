@@ -1,10 +1,16 @@
 use crate::lifecycle::{InitArg, UpgradeArg};
 use crate::state::{DepositEvent, SolanaSignature, SolanaSignatureRange, WithdrawalEvent};
 
+use candid::{CandidType, Nat, Principal};
 use minicbor::{Decode, Encode};
+use serde::Serialize;
 
 /// The event describing the gSol minter state transition.
-#[derive(Clone, Debug, Encode, Decode, PartialEq, Eq)]
+///
+/// Events are persisted as CBOR and replayed on every upgrade: variant and field
+/// indices (`#[n(..)]`) must never be renumbered or reused, and fields added to
+/// this enum or to the nested persisted types must be `Option` so older entries still decode.
+#[derive(Clone, Debug, Encode, Decode, PartialEq, Eq, Serialize)]
 pub enum EventType {
     /// The minter initialization event.
     /// Must be the first event in the log.
@@ -66,7 +72,8 @@ pub enum EventType {
     },
     #[n(11)]
     MintedEvent {
-        /// The minted gSol event.
+        /// The minted gSol event, its `icp_mint_block_index` holds the ledger block of the mint
+        /// and is restored with it on replay.
         #[n(0)]
         event_source: DepositEvent,
     },
@@ -84,9 +91,63 @@ pub enum EventType {
         #[n(0)]
         event_source: WithdrawalEvent,
     },
+    /// Minted and invalid events from slots before `before_slot` were dropped from the state.
+    #[n(14)]
+    PrunedEvents {
+        #[n(0)]
+        before_slot: u64,
+    },
+    /// Scrape the contract history older than the initial signature, down to its first transaction.
+    #[n(15)]
+    BackfillFromGenesis,
+    /// The accepted deposit reached the minting retry limit and was abandoned.
+    #[n(16)]
+    DeadLetterEvent {
+        #[n(0)]
+        event_source: DepositEvent,
+    },
+    /// The abandoned deposit was moved back to the accepted events by a controller.
+    #[n(17)]
+    RetryDeadLetterEvent {
+        #[n(0)]
+        sol_sig: String,
+    },
+    /// The coupon of the redeemed withdrawal was used in the given Solana transaction.
+    #[n(18)]
+    RedemptionSignature {
+        #[n(0)]
+        burn_id: u64,
+        #[n(1)]
+        sol_sig: String,
+    },
+    /// Last known signature of one of the additional contracts.
+    #[n(19)]
+    LastKnownContractSignature {
+        #[n(0)]
+        address: String,
+        #[n(1)]
+        signature: String,
+    },
+    /// The range or signature reached its retry limit and is no longer scraped.
+    #[n(20)]
+    RetryLimitReached {
+        #[n(0)]
+        kind: RetryKind,
+        #[n(1)]
+        key: String,
+    },
+}
+
+/// Kind of work abandoned after reaching its retry limit.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum RetryKind {
+    #[n(0)]
+    SignatureRange,
+    #[n(1)]
+    Signature,
 }
 
-#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, Serialize)]
 pub struct Event {
     /// The canister time at which the minter generated this event.
     #[n(0)]
@@ -95,3 +156,325 @@ pub struct Event {
     #[n(1)]
     pub payload: EventType,
 }
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
+pub struct DepositInfo {
+    pub id: u64,
+    pub from_sol_address: String,
+    pub to_icp_address: Principal,
+    pub amount: Nat,
+    pub sol_sig: String,
+    pub slot: Option<u64>,
+    pub mint_block_index: Option<u64>,
+}
+
+impl From<&DepositEvent> for DepositInfo {
+    fn from(event: &DepositEvent) -> Self {
+        Self {
+            id: event.id,
+            from_sol_address: event.from_sol_address.clone(),
+            to_icp_address: event.to_icp_address,
+            amount: event.amount.clone(),
+            sol_sig: event.sol_sig.clone(),
+            slot: event.slot,
+            mint_block_index: event.get_mint_block_index(),
+        }
+    }
+}
+
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
+pub struct WithdrawalInfo {
+    pub burn_id: u64,
+    pub from_icp_address: Principal,
+    pub to_sol_address: String,
+    pub amount: Nat,
+    pub burn_timestamp: Option<u64>,
+    pub burn_block_index: Option<u64>,
+    pub fee: Option<Nat>,
+    pub redemption_sol_sig: Option<String>,
+}
+
+impl From<&WithdrawalEvent> for WithdrawalInfo {
+    fn from(event: &WithdrawalEvent) -> Self {
+        Self {
+            burn_id: event.get_burn_id(),
+            from_icp_address: event.from_icp_address,
+            to_sol_address: event.to_sol_address.clone(),
+            amount: event.amount.clone(),
+            burn_timestamp: event.get_burn_timestamp(),
+            burn_block_index: event.get_icp_burn_block_index(),
+            fee: event.get_fee().cloned(),
+            redemption_sol_sig: event.get_redemption_sol_sig().cloned(),
+        }
+    }
+}
+
+/// Deposit and withdrawal events of the log exposed to frontends,
+/// `index` is the position in the event log and `timestamp` the time it was recorded.
+#[derive(CandidType, Debug, Clone, PartialEq, Eq)]
+pub enum MinterEvent {
+    Accepted {
+        index: u64,
+        timestamp: u64,
+        deposit: DepositInfo,
+    },
+    Minted {
+        index: u64,
+        timestamp: u64,
+        deposit: DepositInfo,
+    },
+    WithdrawalBurned {
+        index: u64,
+        timestamp: u64,
+        withdrawal: WithdrawalInfo,
+    },
+    WithdrawalRedeemed {
+        index: u64,
+        timestamp: u64,
+        withdrawal: WithdrawalInfo,
+    },
+}
+
+impl Event {
+    /// Converts the event into a `MinterEvent`, `None` for internal bookkeeping events
+    /// and for failed attempts recorded again with a fail reason.
+    pub fn to_minter_event(&self, index: u64) -> Option<MinterEvent> {
+        let timestamp = self.timestamp;
+        match &self.payload {
+            EventType::AcceptedEvent {
+                event_source,
+                fail_reason: None,
+            } => Some(MinterEvent::Accepted {
+                index,
+                timestamp,
+                deposit: event_source.into(),
+            }),
+            EventType::MintedEvent { event_source } => Some(MinterEvent::Minted {
+                index,
+                timestamp,
+                deposit: event_source.into(),
+            }),
+            EventType::WithdrawalBurnedEvent {
+                event_source,
+                fail_reason: None,
+            } => Some(MinterEvent::WithdrawalBurned {
+                index,
+                timestamp,
+                withdrawal: event_source.into(),
+            }),
+            EventType::WithdrawalRedeemedEvent { event_source } => {
+                Some(MinterEvent::WithdrawalRedeemed {
+                    index,
+                    timestamp,
+                    withdrawal: event_source.into(),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lifecycle::RpcHeader;
+    use crate::test_fixtures::{self, principal, SOLANA_ADDRESS, SOLANA_INITIAL_SIGNATURE};
+    use crate::withdraw::{Coupon, CouponHashAlgo};
+    use ic_stable_structures::Storable;
+    use std::borrow::Cow;
+
+    // Adding a variant fails to compile here, the new arm has to take the next index and
+    // `all_event_types` an instance of it.
+    const VARIANT_COUNT: usize = 21;
+
+    fn variant_index(event: &EventType) -> usize {
+        match event {
+            EventType::Init(_) => 0,
+            EventType::Upgrade(_) => 1,
+            EventType::LastKnownSolanaSignature(_) => 2,
+            EventType::LastDepositIdCounter(_) => 3,
+            EventType::LastBurnIdCounter(_) => 4,
+            EventType::NewSolanaSignatureRange(_) => 5,
+            EventType::RemoveSolanaSignatureRange(_) => 6,
+            EventType::RetrySolanaSignatureRange { .. } => 7,
+            EventType::SolanaSignature { .. } => 8,
+            EventType::InvalidEvent { .. } => 9,
+            EventType::AcceptedEvent { .. } => 10,
+            EventType::MintedEvent { .. } => 11,
+            EventType::WithdrawalBurnedEvent { .. } => 12,
+            EventType::WithdrawalRedeemedEvent { .. } => 13,
+            EventType::PrunedEvents { .. } => 14,
+            EventType::BackfillFromGenesis => 15,
+            EventType::DeadLetterEvent { .. } => 16,
+            EventType::RetryDeadLetterEvent { .. } => 17,
+            EventType::RedemptionSignature { .. } => 18,
+            EventType::LastKnownContractSignature { .. } => 19,
+            EventType::RetryLimitReached { .. } => 20,
+        }
+    }
+
+    fn range() -> SolanaSignatureRange {
+        SolanaSignatureRange::new("before".to_string(), Some("until".to_string()))
+            .with_contract_address(Some(SOLANA_ADDRESS.to_string()))
+    }
+
+    fn signature() -> SolanaSignature {
+        SolanaSignature::new(SOLANA_INITIAL_SIGNATURE.to_string(), Some(42))
+            .with_block_time(Some(1_711_616_761))
+            .with_memo(Some(format!("[63] {}", principal(1))))
+    }
+
+    fn deposit() -> DepositEvent {
+        let mut deposit = DepositEvent::new_for_test(7, "sig", principal(1), 1_000, Some(42));
+        deposit.update_mint_block_index(106);
+        deposit
+    }
+
+    fn withdrawal() -> WithdrawalEvent {
+        let mut withdrawal = WithdrawalEvent::new(
+            3,
+            principal(1),
+            SOLANA_ADDRESS.to_string(),
+            Nat::from(100_000u64),
+            Nat::from(1_000u64),
+        );
+        withdrawal.update_after_burn(1_711_616_761_296_437_000, 106);
+        let mut coupon = Coupon::new(
+            "message".to_string(),
+            "hash".to_string(),
+            "signature".to_string(),
+            "public key".to_string(),
+            CouponHashAlgo::Keccak256,
+        );
+        coupon.recovery_id = Some(1);
+        withdrawal.update_after_redeem(coupon);
+        withdrawal.update_redemption_sol_sig("redemption".to_string());
+        withdrawal
+    }
+
+    fn all_event_types() -> Vec<EventType> {
+        let mut init_arg = test_fixtures::init_arg();
+        init_arg.token_decimals = Some(6);
+        init_arg.max_events_per_tick = Some(10);
+        init_arg.solana_rpc_headers = Some(vec![RpcHeader {
+            name: "x-api-key".to_string(),
+            value: "secret".to_string(),
+        }]);
+        init_arg.min_confirmation_slots = Some(32);
+        init_arg.retention_slots = Some(1_000);
+        init_arg.latest_signatures_batch_size = Some(3);
+        init_arg.withdrawal_fee = Some(Nat::from(10u8));
+        init_arg.coupon_hash_algo = Some(CouponHashAlgo::Keccak256);
+        init_arg.authorized_callers = Some(vec![principal(2)]);
+        init_arg.additional_solana_contract_addresses = Some(vec![SOLANA_ADDRESS.to_string()]);
+        init_arg.max_deposit_amount = Some(Nat::from(1_000_000u64));
+        init_arg.fallback_rpc_urls = Some(vec!["https://rpc.ankr.com/solana_devnet"
+            .to_string()
+            .into()]);
+
+        let upgrade_arg = UpgradeArg {
+            solana_rpc_url: Some("https://api.mainnet-beta.solana.com".to_string().into()),
+            solana_contract_address: Some(SOLANA_ADDRESS.to_string()),
+            solana_initial_signature: Some(SOLANA_INITIAL_SIGNATURE.to_string()),
+            ecdsa_key_name: Some("key_1".to_string()),
+            minimum_withdrawal_amount: Some(Nat::from(5_000u64)),
+            ledger_id: Some(principal(3)),
+            max_events_per_tick: Some(20),
+            solana_rpc_headers: Some(vec![]),
+            min_confirmation_slots: Some(64),
+            retention_slots: Some(2_000),
+            reset_all_retries: Some(true),
+            latest_signatures_batch_size: Some(4),
+            withdrawal_fee: Some(Nat::from(20u8)),
+            coupon_hash_algo: Some(CouponHashAlgo::Sha256),
+            authorized_callers: Some(vec![principal(4), principal(5)]),
+            additional_solana_contract_addresses: Some(vec![]),
+            max_deposit_amount: Some(Nat::from(2_000_000u64)),
+            fallback_rpc_urls: Some(vec![]),
+        };
+
+        vec![
+            EventType::Init(init_arg),
+            EventType::Upgrade(upgrade_arg),
+            EventType::LastKnownSolanaSignature(SOLANA_INITIAL_SIGNATURE.to_string()),
+            EventType::LastDepositIdCounter(8),
+            EventType::LastBurnIdCounter(4),
+            EventType::NewSolanaSignatureRange(range()),
+            EventType::RemoveSolanaSignatureRange(SolanaSignatureRange::new(
+                "before".to_string(),
+                None,
+            )),
+            EventType::RetrySolanaSignatureRange {
+                range: range(),
+                failed_sub_range: Some(SolanaSignatureRange::new(
+                    "sub".to_string(),
+                    Some("until".to_string()),
+                )),
+                fail_reason: "timeout".to_string(),
+            },
+            EventType::SolanaSignature {
+                signature: signature(),
+                fail_reason: Some("timeout".to_string()),
+            },
+            EventType::InvalidEvent {
+                signature: signature(),
+                fail_reason: "not a deposit".to_string(),
+            },
+            EventType::AcceptedEvent {
+                event_source: deposit(),
+                fail_reason: Some("ledger unavailable".to_string()),
+            },
+            EventType::MintedEvent {
+                event_source: deposit(),
+            },
+            EventType::WithdrawalBurnedEvent {
+                event_source: withdrawal(),
+                fail_reason: Some("signing failed".to_string()),
+            },
+            EventType::WithdrawalRedeemedEvent {
+                event_source: withdrawal(),
+            },
+            EventType::PrunedEvents { before_slot: 42 },
+            EventType::BackfillFromGenesis,
+            EventType::DeadLetterEvent {
+                event_source: deposit(),
+            },
+            EventType::RetryDeadLetterEvent {
+                sol_sig: "sig".to_string(),
+            },
+            EventType::RedemptionSignature {
+                burn_id: 3,
+                sol_sig: "redemption".to_string(),
+            },
+            EventType::LastKnownContractSignature {
+                address: SOLANA_ADDRESS.to_string(),
+                signature: SOLANA_INITIAL_SIGNATURE.to_string(),
+            },
+            EventType::RetryLimitReached {
+                kind: RetryKind::Signature,
+                key: "sig".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn should_have_an_instance_of_every_variant() {
+        let events = all_event_types();
+        assert_eq!(events.len(), VARIANT_COUNT);
+        for (index, event) in events.iter().enumerate() {
+            assert_eq!(variant_index(event), index, "unexpected variant {event:?}");
+        }
+    }
+
+    #[test]
+    fn should_round_trip_every_variant_through_cbor() {
+        for payload in all_event_types() {
+            let event = Event {
+                timestamp: 1_711_616_761_296_437_000,
+                payload,
+            };
+            let bytes = event.to_bytes().into_owned();
+            assert_eq!(Event::from_bytes(Cow::Owned(bytes)), event);
+        }
+    }
+}
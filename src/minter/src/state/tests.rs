@@ -0,0 +1,1077 @@
+use crate::events::{DepositEvent, SolanaSignature};
+use crate::state::audit::{apply_state_transition, EventType};
+use crate::state::{ChainLag, State};
+use crate::test_fixtures::{self, principal};
+
+// State of the test init arguments after the given events.
+fn replay(events: Vec<EventType>) -> State {
+    let mut state = test_fixtures::state();
+    for event in &events {
+        apply_state_transition(&mut state, event);
+    }
+    state
+}
+
+fn signature(sig: &str, slot: u64) -> EventType {
+    EventType::SolanaSignature {
+        signature: SolanaSignature::new(sig.to_string(), Some(slot)),
+        fail_reason: None,
+    }
+}
+
+mod deposit_confirmation {
+    use super::*;
+
+    #[test]
+    fn should_defer_deposit_close_to_the_tip_until_the_tip_moves_past_it() {
+        let mut state = test_fixtures::state();
+        state.min_confirmation_slots = 32;
+        let deposit = DepositEvent::new_for_test(0, "sig", principal(1), 1_000, Some(1_000));
+
+        // the deposit is the newest transaction of the contract, no later slot is ever observed
+        state.last_observed_slot = Some(1_000);
+        state.latest_chain_slot = Some(1_000);
+        assert!(!state.is_deposit_confirmed(&deposit));
+
+        state.latest_chain_slot = Some(1_031);
+        assert!(!state.is_deposit_confirmed(&deposit));
+
+        state.latest_chain_slot = Some(1_100);
+        assert!(state.is_deposit_confirmed(&deposit));
+    }
+
+    #[test]
+    fn should_accept_deposits_without_slot_or_confirmation_depth() {
+        let mut state = test_fixtures::state();
+        state.min_confirmation_slots = 32;
+        let deposit = DepositEvent::new_for_test(0, "sig", principal(1), 1_000, None);
+        assert!(state.is_deposit_confirmed(&deposit));
+
+        state.min_confirmation_slots = 0;
+        let deposit = DepositEvent::new_for_test(0, "sig", principal(1), 1_000, Some(1_000));
+        assert!(state.is_deposit_confirmed(&deposit));
+    }
+}
+
+mod invalid_events {
+    use super::*;
+
+    fn invalid(sig: &str) -> EventType {
+        EventType::InvalidEvent {
+            signature: SolanaSignature::new(sig.to_string(), Some(10)),
+            fail_reason: "non-Deposit transaction found".to_string(),
+        }
+    }
+
+    #[test]
+    fn should_record_fresh_invalid_event() {
+        let state = replay(vec![signature("sig1", 10), invalid("sig1")]);
+
+        assert!(state.invalid_events.contains_key("sig1"));
+        assert!(state.solana_signatures.is_empty());
+    }
+
+    #[test]
+    fn should_record_invalid_event_scraped_twice() {
+        let state = replay(vec![
+            signature("sig1", 10),
+            invalid("sig1"),
+            // an overlapping range scrapes the signature again
+            signature("sig1", 10),
+            invalid("sig1"),
+        ]);
+
+        assert_eq!(state.invalid_events.len(), 1);
+        assert!(state.solana_signatures.is_empty());
+    }
+}
+
+mod withdrawal_idempotency {
+    use super::*;
+    use crate::constants::WITHDRAWAL_IDEMPOTENCY_WINDOW;
+    use crate::state::WithdrawalRequest;
+    use crate::test_fixtures::SOLANA_ADDRESS;
+    use crate::withdraw::WithdrawError;
+    use candid::Nat;
+
+    const NOW: u64 = 1_711_616_761_296_437_000;
+
+    fn request(amount: u64) -> WithdrawalRequest {
+        WithdrawalRequest {
+            to: SOLANA_ADDRESS.to_string(),
+            amount: Nat::from(amount),
+            from_subaccount: None,
+        }
+    }
+
+    #[test]
+    fn should_burn_once_for_two_identical_keyed_calls() {
+        let mut state = test_fixtures::state();
+        let caller = principal(1);
+        let key = [7; 32];
+
+        // first call: the key is unknown, gSOL is burned and the key recorded
+        assert_eq!(
+            state.idempotent_burn_id(&caller, &key, &request(1_000), NOW),
+            Ok(None)
+        );
+        let burn_id = state.next_burn_id();
+        state.record_idempotency_key(caller, key, request(1_000), burn_id, NOW);
+
+        // the retry gets the burn of the first call instead of burning again
+        assert_eq!(
+            state.idempotent_burn_id(&caller, &key, &request(1_000), NOW + 1),
+            Ok(Some(burn_id))
+        );
+        assert_eq!(state.burn_id_counter, 1);
+    }
+
+    #[test]
+    fn should_reject_key_reused_with_other_arguments() {
+        let mut state = test_fixtures::state();
+        let caller = principal(1);
+        let key = [7; 32];
+        state.record_idempotency_key(caller, key, request(1_000), 0, NOW);
+
+        assert_eq!(
+            state.idempotent_burn_id(&caller, &key, &request(2_000), NOW + 1),
+            Err(WithdrawError::IdempotencyKeyMismatch { burn_id: 0 })
+        );
+        let mut other_subaccount = request(1_000);
+        other_subaccount.from_subaccount = Some([1; 32]);
+        assert_eq!(
+            state.idempotent_burn_id(&caller, &key, &other_subaccount, NOW + 1),
+            Err(WithdrawError::IdempotencyKeyMismatch { burn_id: 0 })
+        );
+    }
+
+    #[test]
+    fn should_scope_keys_to_caller_and_window() {
+        let mut state = test_fixtures::state();
+        let key = [7; 32];
+        state.record_idempotency_key(principal(1), key, request(1_000), 0, NOW);
+
+        assert_eq!(
+            state.idempotent_burn_id(&principal(2), &key, &request(1_000), NOW + 1),
+            Ok(None)
+        );
+        let expired = NOW + WITHDRAWAL_IDEMPOTENCY_WINDOW.as_nanos() as u64;
+        assert_eq!(
+            state.idempotent_burn_id(&principal(1), &key, &request(1_000), expired),
+            Ok(None)
+        );
+    }
+}
+
+mod upgrade {
+    use super::*;
+    use crate::lifecycle::UpgradeArg;
+    use crate::state::InvalidStateError;
+    use candid::Principal;
+
+    #[test]
+    fn should_replay_ledger_id_rotation() {
+        let mut state = test_fixtures::state();
+        let upgrade = UpgradeArg {
+            ledger_id: Some(principal(9)),
+            ..Default::default()
+        };
+
+        apply_state_transition(&mut state, &EventType::Upgrade(upgrade));
+
+        assert_eq!(state.ledger_id, principal(9));
+    }
+
+    #[test]
+    fn should_replace_authorized_callers_on_upgrade() {
+        let upgrade = |callers: Option<Vec<Principal>>| {
+            EventType::Upgrade(UpgradeArg {
+                authorized_callers: callers,
+                ..Default::default()
+            })
+        };
+        let mut state = replay(vec![upgrade(Some(vec![principal(1), principal(2)]))]);
+        assert!(state.is_authorized_caller(&principal(2), false));
+
+        // an upgrade without the list keeps it
+        apply_state_transition(&mut state, &upgrade(None));
+        assert_eq!(
+            state.authorized_callers.iter().copied().collect::<Vec<_>>(),
+            vec![principal(1), principal(2)]
+        );
+
+        apply_state_transition(&mut state, &upgrade(Some(vec![principal(3)])));
+        assert!(!state.is_authorized_caller(&principal(1), false));
+        assert!(!state.is_authorized_caller(&principal(2), false));
+        assert!(state.is_authorized_caller(&principal(3), false));
+    }
+
+    #[test]
+    fn should_encode_authorized_callers_of_the_upgrade_event() {
+        for callers in [None, Some(vec![]), Some(vec![principal(1), principal(2)])] {
+            let event = EventType::Upgrade(UpgradeArg {
+                authorized_callers: callers,
+                ..Default::default()
+            });
+            let bytes = minicbor::to_vec(&event).unwrap();
+            assert_eq!(minicbor::decode::<EventType>(&bytes).unwrap(), event);
+        }
+    }
+
+    #[test]
+    fn should_reject_anonymous_ledger_id() {
+        let mut state = test_fixtures::state();
+        let upgrade = UpgradeArg {
+            ledger_id: Some(Principal::anonymous()),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            state.upgrade(upgrade),
+            Err(InvalidStateError::InvalidLedgerId(_))
+        ));
+    }
+
+    #[test]
+    fn should_reset_all_retry_counters() {
+        use crate::events::SolanaSignatureRange;
+
+        let range = SolanaSignatureRange::new("before".to_string(), Some("until".to_string()));
+        let deposit = DepositEvent::new_for_test(0, "sig2", principal(1), 1_000, Some(10));
+        let accepted = EventType::AcceptedEvent {
+            event_source: deposit,
+            fail_reason: None,
+        };
+        let mut state = replay(vec![
+            EventType::NewSolanaSignatureRange(range.clone()),
+            EventType::RetrySolanaSignatureRange {
+                range,
+                failed_sub_range: None,
+                fail_reason: "timeout".to_string(),
+            },
+            signature("sig1", 10),
+            signature("sig1", 10),
+            signature("sig2", 10),
+            accepted.clone(),
+            accepted,
+        ]);
+        let retries = |state: &State| -> Vec<u8> {
+            state
+                .solana_signature_ranges
+                .values()
+                .map(|r| r.retry.get_retries())
+                .chain(
+                    state
+                        .solana_signatures
+                        .values()
+                        .map(|s| s.retry.get_retries()),
+                )
+                .chain(
+                    state
+                        .accepted_events
+                        .values()
+                        .map(|e| e.retry.get_retries()),
+                )
+                .collect()
+        };
+        assert_eq!(retries(&state), vec![1, 1, 1]);
+
+        let upgrade = UpgradeArg {
+            reset_all_retries: Some(true),
+            ..Default::default()
+        };
+        apply_state_transition(&mut state, &EventType::Upgrade(upgrade));
+
+        assert_eq!(retries(&state), vec![0, 0, 0]);
+    }
+
+    #[test]
+    fn should_replay_contract_address_change() {
+        let address = "9HUZb7ZtqJ5WzHKT4FcMgVAHwWpVb7ZfL7RnBD7QmCjR".to_string();
+        let mut state = test_fixtures::state();
+        let upgrade = UpgradeArg {
+            solana_contract_address: Some(address.clone()),
+            ..Default::default()
+        };
+
+        apply_state_transition(&mut state, &EventType::Upgrade(upgrade));
+
+        assert_eq!(state.solana_contract_address, address);
+    }
+
+    #[test]
+    fn should_reject_empty_contract_address() {
+        let mut state = test_fixtures::state();
+        let upgrade = UpgradeArg {
+            solana_contract_address: Some(" ".to_string()),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            state.upgrade(upgrade),
+            Err(InvalidStateError::InvalidSolanaContractAddress(_))
+        ));
+    }
+}
+
+mod ecdsa_public_key {
+    use super::*;
+    use crate::state::{lazy_call_ecdsa_public_key, read_state};
+
+    #[test]
+    fn should_serve_addresses_from_the_key_fetched_on_demand() {
+        let response = test_fixtures::ecdsa_public_key();
+        let mut state = test_fixtures::state();
+        // the key stored by the first on-demand fetch, the init timer has not run
+        state.ecdsa_public_key = Some(response.clone());
+        test_fixtures::set_state(state);
+
+        // no call to the management canister once the key is known
+        let public_key = futures::executor::block_on(lazy_call_ecdsa_public_key());
+        assert_eq!(public_key.serialize_sec1(true), response.public_key);
+
+        let (compressed, uncompressed) =
+            read_state(|s| (s.compressed_public_key(), s.uncompressed_public_key()));
+        assert_eq!(compressed, hex::encode(&response.public_key));
+        assert_eq!(uncompressed, hex::encode(public_key.serialize_sec1(false)));
+    }
+
+    #[test]
+    fn should_derive_base58_public_key_of_the_minter() {
+        use crate::state::MinterSolanaAddress;
+        use crate::test_fixtures::SOLANA_CONTRACT_ADDRESS;
+
+        let mut state = test_fixtures::state();
+        state.ecdsa_public_key = Some(test_fixtures::ecdsa_public_key());
+
+        // x and y of the public key of the secret [7; 32], without the 0x04 prefix
+        assert_eq!(
+            state.minter_solana_address(),
+            MinterSolanaAddress {
+                deposit_address: SOLANA_CONTRACT_ADDRESS.to_string(),
+                public_key_base58:
+                    "43y5hhS5mWqG4QWeEBqmJfDae1rWstRUETCvcELhzai5ataDC38sCnahANBd5ALUWTMHhPRM74geUt9D3TE5QhG3"
+                        .to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn should_round_trip_der_and_pem_encoded_key() {
+        use crate::state::EncodedPublicKey;
+        use ic_crypto_ecdsa_secp256k1::PublicKey;
+
+        let response = test_fixtures::ecdsa_public_key();
+        let public_key = PublicKey::deserialize_sec1(&response.public_key).unwrap();
+
+        let encoded = EncodedPublicKey::from(&public_key);
+
+        let from_der = PublicKey::deserialize_der(&encoded.der).unwrap();
+        let from_pem = PublicKey::deserialize_pem(&encoded.pem).unwrap();
+        assert_eq!(from_der.serialize_sec1(true), response.public_key);
+        assert_eq!(from_pem.serialize_sec1(true), response.public_key);
+        assert!(encoded.pem.starts_with("-----BEGIN PUBLIC KEY-----"));
+    }
+}
+
+mod ecdsa_public_key_validation {
+    use super::*;
+    use crate::state::validate_ecdsa_public_key;
+    use ic_crypto_ecdsa_secp256k1::PublicKey;
+
+    #[test]
+    fn should_accept_key_round_tripping_between_encodings() {
+        let response = test_fixtures::ecdsa_public_key();
+        let public_key = PublicKey::deserialize_sec1(&response.public_key).unwrap();
+
+        let uncompressed = public_key.serialize_sec1(false);
+        assert_eq!(
+            PublicKey::deserialize_sec1(&uncompressed)
+                .unwrap()
+                .serialize_sec1(true),
+            response.public_key
+        );
+        validate_ecdsa_public_key(&response, &public_key);
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_reject_key_that_is_not_compressed() {
+        let mut response = test_fixtures::ecdsa_public_key();
+        let public_key = PublicKey::deserialize_sec1(&response.public_key).unwrap();
+        response.public_key = public_key.serialize_sec1(false);
+
+        validate_ecdsa_public_key(&response, &public_key);
+    }
+}
+
+mod event_replay {
+    use super::*;
+
+    fn deposit(sig: &str, amount: u64) -> DepositEvent {
+        deposit_at(sig, amount, 10)
+    }
+
+    fn deposit_at(sig: &str, amount: u64, slot: u64) -> DepositEvent {
+        DepositEvent::new_for_test(0, sig, principal(1), amount, Some(slot))
+    }
+
+    fn accepted(deposit: &DepositEvent) -> EventType {
+        EventType::AcceptedEvent {
+            event_source: deposit.clone(),
+            fail_reason: None,
+        }
+    }
+
+    fn minted(deposit: &DepositEvent, block_index: u64) -> EventType {
+        let mut deposit = deposit.clone();
+        deposit.update_mint_block_index(block_index);
+        EventType::MintedEvent {
+            event_source: deposit,
+        }
+    }
+
+    #[test]
+    fn should_mint_deposit_end_to_end() {
+        let deposit = deposit("sig1", 1_000);
+
+        let state = replay(vec![
+            signature("sig1", 10),
+            accepted(&deposit),
+            minted(&deposit, 5),
+        ]);
+
+        assert!(state.solana_signatures.is_empty());
+        assert!(state.accepted_events.is_empty());
+        assert_eq!(state.minted_events["sig1"].get_mint_block_index(), Some(5));
+    }
+
+    #[test]
+    fn should_restore_mint_block_index_from_the_persisted_events() {
+        let deposit = deposit("sig1", 1_000);
+        let persisted: Vec<EventType> = vec![
+            signature("sig1", 10),
+            accepted(&deposit),
+            minted(&deposit, 42),
+        ]
+        .iter()
+        .map(|event| minicbor::decode(&minicbor::to_vec(event).unwrap()).unwrap())
+        .collect();
+
+        let state = replay(persisted);
+
+        assert_eq!(state.minted_events["sig1"].get_mint_block_index(), Some(42));
+    }
+
+    // Events minting `old` at slot 10 and `new` at slot 100, with an invalid signature at slot 10.
+    fn minted_old_and_new() -> Vec<EventType> {
+        let (old, new) = (deposit_at("old", 1_000, 10), deposit_at("new", 2_000, 100));
+        vec![
+            signature("old", 10),
+            accepted(&old),
+            minted(&old, 1),
+            signature("new", 100),
+            accepted(&new),
+            minted(&new, 2),
+            signature("invalid", 10),
+            EventType::InvalidEvent {
+                signature: SolanaSignature::new("invalid".to_string(), Some(10)),
+                fail_reason: "non-Deposit transaction found".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn should_prune_old_events_and_keep_the_retained_ones() {
+        let mut events = minted_old_and_new();
+        events.push(EventType::PrunedEvents { before_slot: 50 });
+
+        let state = replay(events);
+
+        assert_eq!(state.minted_events.keys().collect::<Vec<_>>(), vec!["new"]);
+        assert!(state.invalid_events.is_empty());
+        assert_eq!(state.pruned_before_slot, Some(50));
+        assert!(state.is_pruned_slot(10));
+        assert!(!state.is_pruned_slot(100));
+        assert!(!state.has_prunable_events(50));
+    }
+
+    #[test]
+    fn should_replay_pruning_to_an_equivalent_state() {
+        let mut events = minted_old_and_new();
+        events.push(EventType::PrunedEvents { before_slot: 50 });
+        // a lower high-water mark of a later prune does not move it back
+        events.push(EventType::PrunedEvents { before_slot: 20 });
+
+        let new = deposit_at("new", 2_000, 100);
+        let retained_only = replay(vec![
+            signature("new", 100),
+            accepted(&new),
+            minted(&new, 2),
+            EventType::PrunedEvents { before_slot: 50 },
+        ]);
+
+        assert!(replay(events).is_equivalent_to(&retained_only));
+    }
+
+    #[test]
+    fn should_enumerate_events_in_key_order_regardless_of_replay_order() {
+        let deposits: Vec<DepositEvent> = ["sig3", "sig1", "sig2"]
+            .iter()
+            .map(|sig| deposit(sig, 1_000))
+            .collect();
+        let events = |deposits: &[DepositEvent]| -> Vec<EventType> {
+            deposits
+                .iter()
+                .flat_map(|d| vec![signature(&d.sol_sig, 10), accepted(d)])
+                .collect()
+        };
+
+        let forward = replay(events(&deposits));
+        let mut reversed_deposits = deposits.clone();
+        reversed_deposits.reverse();
+        let reversed = replay(events(&reversed_deposits));
+
+        assert!(forward.is_equivalent_to(&reversed));
+        assert_eq!(forward.canonical_hash(), reversed.canonical_hash());
+        assert_eq!(
+            forward.accepted_events.keys().collect::<Vec<_>>(),
+            vec!["sig1", "sig2", "sig3"]
+        );
+    }
+
+    #[test]
+    fn should_move_exhausted_deposit_to_dead_letters_and_revive_it() {
+        use crate::constants::MINT_GSOL_RETRY_LIMIT;
+
+        let deposit = deposit("sig1", 1_000);
+        let mut events = vec![signature("sig1", 10)];
+        // every failed mint records the accepted event again
+        events.extend((0..=MINT_GSOL_RETRY_LIMIT).map(|_| accepted(&deposit)));
+        let mut state = replay(events);
+        let exhausted = state.accepted_events["sig1"].clone();
+        assert!(exhausted
+            .retry
+            .is_retry_limit_reached(MINT_GSOL_RETRY_LIMIT));
+
+        apply_state_transition(
+            &mut state,
+            &EventType::DeadLetterEvent {
+                event_source: exhausted,
+            },
+        );
+        assert!(state.accepted_events.is_empty());
+        assert!(state.dead_letter_events.contains_key("sig1"));
+
+        apply_state_transition(
+            &mut state,
+            &EventType::RetryDeadLetterEvent {
+                sol_sig: "sig1".to_string(),
+            },
+        );
+        assert!(state.dead_letter_events.is_empty());
+        assert_eq!(state.accepted_events["sig1"].retry.get_retries(), 0);
+    }
+
+    #[test]
+    fn should_replay_backfill_from_genesis_without_until() {
+        let state = replay(vec![EventType::BackfillFromGenesis]);
+
+        let ranges: Vec<_> = state.solana_signature_ranges.values().collect();
+        assert_eq!(ranges.len(), 1);
+        assert_eq!(
+            ranges[0].before_sol_sig,
+            test_fixtures::SOLANA_INITIAL_SIGNATURE
+        );
+        assert_eq!(ranges[0].until_sol_sig, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempted to backfill from genesis twice")]
+    fn should_not_backfill_from_genesis_twice() {
+        replay(vec![
+            EventType::BackfillFromGenesis,
+            EventType::BackfillFromGenesis,
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Attempted to remove NON existing accepted event")]
+    fn should_not_mint_deposit_twice() {
+        let deposit = deposit("sig1", 1_000);
+
+        replay(vec![
+            signature("sig1", 10),
+            accepted(&deposit),
+            minted(&deposit, 5),
+            minted(&deposit, 6),
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Minted amount differs from accepted deposit amount: sig1")]
+    fn should_reject_log_minting_another_amount_than_accepted() {
+        let tampered = deposit("sig1", 1_000_000);
+        let deposit = deposit("sig1", 1_000);
+
+        replay(vec![
+            signature("sig1", 10),
+            accepted(&deposit),
+            minted(&tampered, 5),
+        ]);
+    }
+
+    #[test]
+    fn should_replay_slot_and_block_time_of_signatures() {
+        let event = EventType::SolanaSignature {
+            signature: SolanaSignature::new("sig1".to_string(), Some(10))
+                .with_block_time(Some(1_711_616_761)),
+            fail_reason: None,
+        };
+        // events are replayed from their persisted encoding
+        let decoded: EventType = minicbor::decode(&minicbor::to_vec(&event).unwrap()).unwrap();
+
+        let state = replay(vec![decoded]);
+
+        let signature = &state.solana_signatures["sig1"];
+        assert_eq!(signature.slot, Some(10));
+        assert_eq!(signature.block_time, Some(1_711_616_761));
+    }
+
+    #[test]
+    fn should_report_lag_of_the_tip_behind_the_last_minted_deposit() {
+        let older = deposit_at("sig1", 1_000, 10);
+        let newer = deposit_at("sig2", 1_000, 40);
+        let mut state = replay(vec![
+            signature("sig1", 10),
+            signature("sig2", 40),
+            accepted(&older),
+            accepted(&newer),
+            minted(&newer, 5),
+            minted(&older, 6),
+        ]);
+        assert_eq!(state.chain_lag().lag_slots, None);
+
+        state.latest_chain_slot = Some(100);
+
+        assert_eq!(
+            state.chain_lag(),
+            ChainLag {
+                latest_slot: Some(100),
+                last_minted_slot: Some(40),
+                lag_slots: Some(60),
+            }
+        );
+    }
+}
+
+mod task_metrics {
+    use super::*;
+    use crate::state::{TaskMetrics, TaskType};
+
+    #[test]
+    fn should_record_duration_of_completed_run() {
+        let mut state = test_fixtures::state();
+        state.active_tasks.insert(TaskType::MintGSol);
+
+        state.record_task_run(TaskType::MintGSol, 1_000, 4_500, 2_000_000);
+
+        let status = state
+            .task_statuses()
+            .into_iter()
+            .find(|status| status.task == TaskType::MintGSol)
+            .unwrap();
+        assert!(!status.active);
+        assert_eq!(
+            status.metrics,
+            Some(TaskMetrics {
+                last_run_timestamp: 1_000,
+                last_duration_ns: 3_500,
+                last_instructions: 2_000_000,
+            })
+        );
+    }
+}
+
+mod config_validation {
+    use super::*;
+    use crate::constants::MAX_MINIMUM_WITHDRAWAL_TOKENS;
+    use crate::state::InvalidStateError;
+    use candid::Nat;
+
+    fn state_with_initial_signature(signature: &str) -> Result<State, InvalidStateError> {
+        let mut arg = test_fixtures::init_arg();
+        arg.solana_initial_signature = signature.to_string();
+        State::try_from(arg)
+    }
+
+    fn state_with_minimum_withdrawal_amount(minimum: u128) -> Result<State, InvalidStateError> {
+        let mut arg = test_fixtures::init_arg();
+        arg.minimum_withdrawal_amount = Nat::from(minimum);
+        State::try_from(arg)
+    }
+
+    fn state_with_ecdsa_key_name(name: &str) -> Result<State, InvalidStateError> {
+        let mut arg = test_fixtures::init_arg();
+        arg.ecdsa_key_name = name.to_string();
+        State::try_from(arg)
+    }
+
+    #[test]
+    fn should_accept_every_known_ecdsa_key_name() {
+        for name in ["dfx_test_key", "test_key_1", "key_1"] {
+            assert_eq!(
+                state_with_ecdsa_key_name(name).map(|s| s.ecdsa_key_name),
+                Ok(name.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_unknown_ecdsa_key_name() {
+        for name in ["key_2", "Key_1", " "] {
+            assert!(matches!(
+                state_with_ecdsa_key_name(name),
+                Err(InvalidStateError::InvalidEcdsaKeyName(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn should_reject_empty_rpc_url() {
+        let mut arg = test_fixtures::init_arg();
+        arg.solana_rpc_url = " ".to_string().into();
+
+        assert!(matches!(
+            State::try_from(arg),
+            Err(InvalidStateError::InvalidSolanaRpcUrl(_))
+        ));
+    }
+
+    #[test]
+    fn should_reject_too_small_minimum_withdrawal_amount() {
+        assert!(matches!(
+            state_with_minimum_withdrawal_amount(0),
+            Err(InvalidStateError::InvalidMinimumWithdrawalAmount(_))
+        ));
+
+        // the ledger fee is only known after install
+        let state = state_with_minimum_withdrawal_amount(5_000).unwrap();
+        assert!(state.is_minimum_withdrawal_below_ledger_fee(&Nat::from(10_000u64)));
+        assert!(!state.is_minimum_withdrawal_below_ledger_fee(&Nat::from(5_000u64)));
+    }
+
+    #[test]
+    fn should_reject_too_large_minimum_withdrawal_amount() {
+        // the test init arguments use SOL decimals
+        let ceiling = MAX_MINIMUM_WITHDRAWAL_TOKENS as u128 * 1_000_000_000;
+
+        assert!(state_with_minimum_withdrawal_amount(ceiling).is_ok());
+        assert!(matches!(
+            state_with_minimum_withdrawal_amount(ceiling + 1),
+            Err(InvalidStateError::InvalidMinimumWithdrawalAmount(_))
+        ));
+    }
+
+    #[test]
+    fn should_accept_valid_initial_signature() {
+        assert!(state_with_initial_signature(test_fixtures::SOLANA_INITIAL_SIGNATURE).is_ok());
+    }
+
+    #[test]
+    fn should_reject_empty_initial_signature() {
+        assert!(matches!(
+            state_with_initial_signature(" "),
+            Err(InvalidStateError::InvalidSolanaInitialSignature(_))
+        ));
+    }
+
+    #[test]
+    fn should_reject_too_short_initial_signature() {
+        let too_short = &test_fixtures::SOLANA_INITIAL_SIGNATURE[..40];
+
+        assert!(matches!(
+            state_with_initial_signature(too_short),
+            Err(InvalidStateError::InvalidSolanaInitialSignature(_))
+        ));
+    }
+
+    #[test]
+    fn should_reject_initial_signature_that_is_not_base58() {
+        assert!(matches!(
+            state_with_initial_signature("0OIl"),
+            Err(InvalidStateError::InvalidSolanaInitialSignature(_))
+        ));
+    }
+}
+
+mod minting_circuit_breaker {
+    use super::*;
+    use crate::constants::{MINTING_CIRCUIT_BREAKER_THRESHOLD, MINTING_PAUSE_COOLDOWN};
+
+    const NOW: u64 = 1_711_616_761_296_437_000;
+
+    #[test]
+    fn should_trip_after_consecutive_failures() {
+        let mut state = test_fixtures::state();
+
+        for _ in 1..MINTING_CIRCUIT_BREAKER_THRESHOLD {
+            assert!(!state.record_ledger_call(false, NOW));
+        }
+        assert!(!state.is_minting_paused(NOW));
+
+        assert!(state.record_ledger_call(false, NOW));
+        assert!(state.is_minting_paused(NOW));
+        // minting resumes by itself after the cooldown
+        assert!(!state.is_minting_paused(NOW + MINTING_PAUSE_COOLDOWN.as_nanos() as u64));
+    }
+
+    #[test]
+    fn should_reset_count_on_success() {
+        let mut state = test_fixtures::state();
+
+        for _ in 1..MINTING_CIRCUIT_BREAKER_THRESHOLD {
+            state.record_ledger_call(false, NOW);
+        }
+        state.record_ledger_call(true, NOW);
+
+        assert!(!state.record_ledger_call(false, NOW));
+        assert!(!state.is_minting_paused(NOW));
+    }
+
+    #[test]
+    fn should_resume_minting_manually() {
+        let mut state = test_fixtures::state();
+        for _ in 0..MINTING_CIRCUIT_BREAKER_THRESHOLD {
+            state.record_ledger_call(false, NOW);
+        }
+
+        state.resume_minting();
+
+        assert!(!state.is_minting_paused(NOW));
+    }
+}
+
+mod authorization {
+    use super::*;
+    use candid::Principal;
+
+    #[test]
+    fn should_reject_callers_that_are_not_authorized() {
+        let mut arg = test_fixtures::init_arg();
+        arg.authorized_callers = Some(vec![principal(1)]);
+        let state = State::try_from(arg).unwrap();
+
+        assert!(state.is_authorized_caller(&principal(1), false));
+        assert!(!state.is_authorized_caller(&principal(2), false));
+        assert!(!state.is_authorized_caller(&Principal::anonymous(), false));
+        // controllers need not be listed
+        assert!(state.is_authorized_caller(&principal(2), true));
+    }
+
+    #[test]
+    fn should_authorize_only_controllers_by_default() {
+        let state = test_fixtures::state();
+
+        assert!(state.authorized_callers.is_empty());
+        assert!(!state.is_authorized_caller(&principal(1), false));
+        assert!(state.is_authorized_caller(&principal(1), true));
+    }
+}
+
+mod signature_range_progress {
+    use super::*;
+    use crate::events::SolanaSignatureRange;
+    use crate::state::RangeCursor;
+
+    #[test]
+    fn should_advance_the_cursor_across_scans() {
+        let range = SolanaSignatureRange::new("before".to_string(), Some("until".to_string()));
+        let mut state = replay(vec![EventType::NewSolanaSignatureRange(range.clone())]);
+        let progress = |state: &State| {
+            let ranges = state.signature_range_progress();
+            assert_eq!(ranges.len(), 1);
+            assert_eq!(ranges[0].before_sol_sig, "before");
+            assert_eq!(ranges[0].until_sol_sig.as_deref(), Some("until"));
+            ranges[0].progress.clone()
+        };
+        assert_eq!(progress(&state), None);
+
+        state.record_range_cursor(&range, "chunk1", 100);
+        assert_eq!(
+            progress(&state),
+            Some(RangeCursor {
+                cursor: "chunk1".to_string(),
+                scanned_signatures: 100,
+            })
+        );
+
+        state.record_range_cursor(&range, "chunk2", 200);
+        assert_eq!(
+            progress(&state),
+            Some(RangeCursor {
+                cursor: "chunk2".to_string(),
+                scanned_signatures: 200,
+            })
+        );
+
+        // a completed range is no longer reported
+        apply_state_transition(&mut state, &EventType::RemoveSolanaSignatureRange(range));
+        assert!(state.signature_range_progress().is_empty());
+        assert!(state.range_cursors.is_empty());
+    }
+}
+
+mod config {
+    use super::*;
+    use crate::constants::{
+        GET_LATEST_SOLANA_SIGNATURE, MINT_GSOL, SCRAPPING_SOLANA_SIGNATURES,
+        SCRAPPING_SOLANA_SIGNATURE_RANGES,
+    };
+    use crate::lifecycle::SolanaNetwork;
+    use crate::state::MinterConfig;
+    use crate::test_fixtures::{LEDGER_ID, SOLANA_CONTRACT_ADDRESS, SOLANA_INITIAL_SIGNATURE};
+    use crate::withdraw::CouponHashAlgo;
+    use candid::{Nat, Principal};
+
+    #[test]
+    fn should_return_the_configuration_of_the_state() {
+        let state = test_fixtures::state();
+
+        assert_eq!(
+            state.config(),
+            MinterConfig {
+                solana_network: SolanaNetwork::Devnet,
+                solana_contract_address: SOLANA_CONTRACT_ADDRESS.to_string(),
+                solana_initial_signature: SOLANA_INITIAL_SIGNATURE.to_string(),
+                ledger_id: Principal::from_text(LEDGER_ID).unwrap(),
+                ecdsa_key_name: "test_key_1".to_string(),
+                minimum_withdrawal_amount: Nat::from(1_000u64),
+                withdrawal_fee: Nat::from(0u64),
+                coupon_hash_algo: CouponHashAlgo::Sha256,
+                token_decimals: state.token_decimals,
+                max_events_per_tick: state.max_events_per_tick,
+                latest_signatures_batch_size: state.latest_signatures_batch_size,
+                min_confirmation_slots: state.min_confirmation_slots,
+                retention_slots: state.retention_slots,
+                get_latest_signature_interval_secs: GET_LATEST_SOLANA_SIGNATURE.as_secs(),
+                scrap_signature_ranges_interval_secs: SCRAPPING_SOLANA_SIGNATURE_RANGES.as_secs(),
+                scrap_signatures_interval_secs: SCRAPPING_SOLANA_SIGNATURES.as_secs(),
+                mint_gsol_interval_secs: MINT_GSOL.as_secs(),
+            }
+        );
+    }
+}
+
+mod id_counters {
+    use super::*;
+
+    #[test]
+    fn should_hand_out_ids_up_to_the_last_one() {
+        let mut state = test_fixtures::state();
+        state.deposit_id_counter = u64::MAX - 1;
+        state.burn_id_counter = u64::MAX - 1;
+
+        assert_eq!(state.next_deposit_id(), u64::MAX - 1);
+        assert_eq!(state.next_burn_id(), u64::MAX - 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "deposit id counter overflow")]
+    fn should_trap_on_deposit_id_overflow() {
+        let mut state = test_fixtures::state();
+        state.deposit_id_counter = u64::MAX;
+
+        state.next_deposit_id();
+    }
+
+    #[test]
+    #[should_panic(expected = "burn id counter overflow")]
+    fn should_trap_on_burn_id_overflow() {
+        let mut state = test_fixtures::state();
+        state.burn_id_counter = u64::MAX;
+
+        state.next_burn_id();
+    }
+
+    #[test]
+    fn should_wrap_request_ids() {
+        let mut state = test_fixtures::state();
+        state.http_request_counter = u64::MAX;
+
+        assert_eq!(state.next_request_id(), u64::MAX);
+        assert_eq!(state.next_request_id(), 0);
+    }
+}
+
+mod signature_ranges {
+    use super::*;
+    use crate::events::SolanaSignatureRange;
+
+    fn range() -> SolanaSignatureRange {
+        SolanaSignatureRange::new("before".to_string(), Some("until".to_string()))
+    }
+
+    #[test]
+    fn should_ignore_removing_a_completed_range() {
+        let mut state = replay(vec![EventType::NewSolanaSignatureRange(range())]);
+
+        // overlapping scans complete the same range twice
+        apply_state_transition(&mut state, &EventType::RemoveSolanaSignatureRange(range()));
+        apply_state_transition(&mut state, &EventType::RemoveSolanaSignatureRange(range()));
+
+        assert!(state.solana_signature_ranges.is_empty());
+    }
+
+    #[test]
+    fn should_ignore_retrying_a_completed_range() {
+        let mut state = test_fixtures::state();
+        let sub_range = SolanaSignatureRange::new("middle".to_string(), Some("until".to_string()));
+
+        apply_state_transition(
+            &mut state,
+            &EventType::RetrySolanaSignatureRange {
+                range: range(),
+                failed_sub_range: Some(sub_range),
+                fail_reason: "timeout".to_string(),
+            },
+        );
+
+        // the completed range is not brought back
+        assert!(state.solana_signature_ranges.is_empty());
+    }
+}
+
+mod state_hash {
+    use super::*;
+
+    #[test]
+    fn should_keep_the_hash_of_an_unchanged_state() {
+        let state = replay(vec![signature("sig1", 10)]);
+        let hash = state.canonical_hash();
+
+        assert_eq!(state.canonical_hash(), hash);
+        assert_eq!(replay(vec![signature("sig1", 10)]).canonical_hash(), hash);
+
+        // runtime-only bookkeeping is not rebuilt from the log
+        let mut observed = replay(vec![signature("sig1", 10)]);
+        observed.latest_chain_slot = Some(100);
+        assert!(observed.is_equivalent_to(&state));
+        assert_eq!(observed.canonical_hash(), hash);
+    }
+
+    #[test]
+    fn should_change_the_hash_of_a_mutated_state() {
+        let state = replay(vec![signature("sig1", 10)]);
+
+        let mut mutated = replay(vec![signature("sig1", 10)]);
+        mutated.withdrawal_fee += 1u8;
+
+        assert!(!mutated.is_equivalent_to(&state));
+        assert_ne!(mutated.canonical_hash(), state.canonical_hash());
+        assert_ne!(
+            replay(vec![signature("sig2", 10)]).canonical_hash(),
+            state.canonical_hash()
+        );
+    }
+}
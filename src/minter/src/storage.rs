@@ -1,3 +1,4 @@
+use crate::constants::MAX_EVENTS_EXPORT_SIZE;
 use crate::state::event::{Event, EventType};
 use ic_stable_structures::{
     log::Log as StableLog,
@@ -69,6 +70,11 @@ pub fn get_storage_events() -> Vec<Event> {
     events
 }
 
+/// Returns the event at the given index of the event log.
+pub fn get_event(index: u64) -> Option<Event> {
+    EVENTS.with(|events| events.borrow().get(index))
+}
+
 /// Returns the total number of events in the audit log.
 pub fn total_event_count() -> u64 {
     EVENTS.with(|events| events.borrow().len())
@@ -80,3 +86,60 @@ where
 {
     EVENTS.with(|events| f(Box::new(events.borrow().iter())))
 }
+
+/// Serializes up to `length` events from index `start` as a JSON array of
+/// `{"index", "timestamp", "payload"}` objects. The page ends early rather than
+/// exceeding `MAX_EVENTS_EXPORT_SIZE` bytes.
+pub fn export_events_json(start: u64, length: u64) -> String {
+    let mut entries: Vec<String> = Vec::new();
+    let mut size = 0;
+
+    EVENTS.with(|events| {
+        let events = events.borrow();
+        let end = start.saturating_add(length).min(events.len());
+        for index in start..end {
+            let event = events.get(index).expect("event index should be in range");
+            let entry = serde_json::json!({
+                "index": index,
+                "timestamp": event.timestamp,
+                "payload": event.payload,
+            })
+            .to_string();
+            // the first event is always returned, so the export cannot get stuck
+            if !entries.is_empty() && size + entry.len() > MAX_EVENTS_EXPORT_SIZE {
+                break;
+            }
+            size += entry.len() + 1;
+            entries.push(entry);
+        }
+    });
+
+    format!("[{}]", entries.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_get_event_by_index() {
+        // `record_event` reads the canister time, so the log is filled directly
+        let events: Vec<Event> = (0..3)
+            .map(|i| Event {
+                timestamp: 1_711_616_761_000_000_000 + i,
+                payload: EventType::RetryDeadLetterEvent {
+                    sol_sig: format!("sig{i}"),
+                },
+            })
+            .collect();
+        EVENTS.with(|log| {
+            for event in &events {
+                log.borrow().append(event).unwrap();
+            }
+        });
+
+        assert_eq!(get_event(1), Some(events[1].clone()));
+        assert_eq!(get_event(total_event_count()), None);
+        assert_eq!(get_event(u64::MAX), None);
+    }
+}
@@ -0,0 +1,59 @@
+use crate::lifecycle::InitArg;
+use crate::state::{State, STATE};
+
+use candid::{Nat, Principal};
+use ic_cdk::api::management_canister::ecdsa::EcdsaPublicKeyResponse;
+
+pub const SOLANA_CONTRACT_ADDRESS: &str = "AAJL4DeXnWBNRowWjvpkAgwtAACpz6NfaA1T2p8Hrpy8";
+pub const SOLANA_INITIAL_SIGNATURE: &str =
+    "dhCUjkhdmEw18r1uNVxKanMyEXYmdcp56UBUmLXkgnPU8QFoHJYKy4UMrHLHk7oxUWXc13gvcL11ivCNJADaMJN";
+pub const SOLANA_RPC_URL: &str = "https://api.devnet.solana.com";
+pub const SOLANA_ADDRESS: &str = "HS6NTv6GBVSLct8dsimRWRvjczJTAgfgDJt8VpR8wtGm";
+pub const LEDGER_ID: &str = "mxzaz-hqaaa-aaaar-qaada-cai";
+
+pub fn init_arg() -> InitArg {
+    InitArg {
+        solana_rpc_url: SOLANA_RPC_URL.to_string().into(),
+        solana_contract_address: SOLANA_CONTRACT_ADDRESS.to_string(),
+        solana_initial_signature: SOLANA_INITIAL_SIGNATURE.to_string(),
+        ecdsa_key_name: "test_key_1".to_string(),
+        ledger_id: Principal::from_text(LEDGER_ID).unwrap(),
+        minimum_withdrawal_amount: Nat::from(1_000u64),
+        token_decimals: None,
+        max_events_per_tick: None,
+        solana_rpc_headers: None,
+        min_confirmation_slots: None,
+        retention_slots: None,
+        latest_signatures_batch_size: None,
+        withdrawal_fee: None,
+        coupon_hash_algo: None,
+        authorized_callers: None,
+        additional_solana_contract_addresses: None,
+        max_deposit_amount: None,
+        fallback_rpc_urls: None,
+    }
+}
+
+pub fn state() -> State {
+    State::try_from(init_arg()).expect("valid init arg")
+}
+
+// Installs the state read by `read_state` and `mutate_state` on the current test thread.
+pub fn set_state(state: State) {
+    STATE.with(|s| *s.borrow_mut() = Some(state));
+}
+
+// Public key of the minter as returned by the management canister (compressed SEC1).
+pub fn ecdsa_public_key() -> EcdsaPublicKeyResponse {
+    let secret_key = libsecp256k1::SecretKey::parse(&[7; 32]).expect("valid secret key");
+    EcdsaPublicKeyResponse {
+        public_key: libsecp256k1::PublicKey::from_secret_key(&secret_key)
+            .serialize_compressed()
+            .to_vec(),
+        chain_code: vec![0; 32],
+    }
+}
+
+pub fn principal(byte: u8) -> Principal {
+    Principal::from_slice(&[byte; 29])
+}
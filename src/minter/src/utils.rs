@@ -1,25 +1,30 @@
-use std::{collections::HashMap, fmt::Display, hash::Hash};
+use std::fmt::Display;
 
-pub struct HashMapUtils;
+// Helpers shared by the HashMap and BTreeMap backed stores of the state.
+pub struct MapUtils;
 
-impl HashMapUtils {
-    pub fn format_keys_as_string<K, V>(keys: &HashMap<K, V>) -> String
+impl MapUtils {
+    pub fn format_keys_as_string<'a, M, K, V>(map: &'a M) -> String
     where
-        K: Display,
+        &'a M: IntoIterator<Item = (&'a K, &'a V)>,
+        K: Display + 'a,
+        V: 'a,
     {
-        keys.keys()
-            .map(|k| k.to_string())
+        map.into_iter()
+            .map(|(k, _)| k.to_string())
             .collect::<Vec<String>>()
             .join("\n")
     }
 
-    pub fn filter<K, V, F>(map: &HashMap<K, V>, filter: F) -> HashMap<K, V>
+    pub fn filter<'a, M, K, V, F>(map: &'a M, filter: F) -> M
     where
-        K: Clone + Eq + Hash,
-        V: Clone,
+        &'a M: IntoIterator<Item = (&'a K, &'a V)>,
+        M: FromIterator<(K, V)>,
+        K: Clone + 'a,
+        V: Clone + 'a,
         F: Fn(&V) -> bool,
     {
-        map.iter()
+        map.into_iter()
             .filter_map(|(key, value)| {
                 if filter(value) {
                     Some((key.clone(), value.clone()))
@@ -29,6 +34,34 @@ impl HashMapUtils {
             })
             .collect()
     }
+
+    // Splits the map into (kept, dropped) entries in a single pass.
+    pub fn partition<'a, M, K, V, F>(map: &'a M, filter: F) -> (M, M)
+    where
+        &'a M: IntoIterator<Item = (&'a K, &'a V)>,
+        M: Default + Extend<(K, V)>,
+        K: Clone + 'a,
+        V: Clone + 'a,
+        F: Fn(&V) -> bool,
+    {
+        map.into_iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .partition(|(_, value)| filter(value))
+    }
+
+    // Keeps at most `limit` entries, preferring the ones with the smallest sort key.
+    pub fn limit_by<M, K, V, F, O>(map: M, limit: usize, sort_key: F) -> M
+    where
+        M: IntoIterator<Item = (K, V)> + FromIterator<(K, V)>,
+        F: Fn(&V) -> O,
+        O: Ord,
+    {
+        let mut entries: Vec<(K, V)> = map.into_iter().collect();
+        // stable sort, so equal keys keep the map's iteration order
+        entries.sort_by_key(|(_, value)| sort_key(value));
+        entries.truncate(limit);
+        entries.into_iter().collect()
+    }
 }
 
 pub struct VecUtils;
@@ -44,3 +77,123 @@ impl VecUtils {
             .join("\n")
     }
 }
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+pub struct Base58Utils;
+
+impl Base58Utils {
+    // Decodes a base58 (bitcoin alphabet) string, as used for Solana addresses and signatures.
+    pub fn decode(input: &str) -> Option<Vec<u8>> {
+        // little-endian accumulator of the decoded number
+        let mut bytes: Vec<u8> = Vec::with_capacity(input.len());
+
+        for c in input.bytes() {
+            let mut carry = BASE58_ALPHABET.iter().position(|&a| a == c)? as u32;
+            for byte in bytes.iter_mut() {
+                carry += (*byte as u32) * 58;
+                *byte = (carry & 0xff) as u8;
+                carry >>= 8;
+            }
+            while carry > 0 {
+                bytes.push((carry & 0xff) as u8);
+                carry >>= 8;
+            }
+        }
+
+        // every leading '1' encodes a leading zero byte
+        let leading_zeros = input.bytes().take_while(|&c| c == b'1').count();
+        bytes.extend(std::iter::repeat(0).take(leading_zeros));
+        bytes.reverse();
+
+        Some(bytes)
+    }
+
+    // Encodes bytes as base58 (bitcoin alphabet), the inverse of `decode`.
+    pub fn encode(input: &[u8]) -> String {
+        // little-endian base58 digits of the encoded number
+        let mut digits: Vec<u8> = Vec::with_capacity(input.len() * 138 / 100 + 1);
+
+        for &byte in input {
+            let mut carry = byte as u32;
+            for digit in digits.iter_mut() {
+                carry += (*digit as u32) << 8;
+                *digit = (carry % 58) as u8;
+                carry /= 58;
+            }
+            while carry > 0 {
+                digits.push((carry % 58) as u8);
+                carry /= 58;
+            }
+        }
+
+        // every leading zero byte is encoded as a '1'
+        let leading_zeros = input.iter().take_while(|&&b| b == 0).count();
+        std::iter::repeat('1')
+            .take(leading_zeros)
+            .chain(
+                digits
+                    .iter()
+                    .rev()
+                    .map(|&d| BASE58_ALPHABET[d as usize] as char),
+            )
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn should_drain_backlog_larger_than_the_cap_over_multiple_ticks() {
+        let cap = 10;
+        let mut backlog: HashMap<u64, u64> = (0..25).map(|id| (id, 0)).collect();
+        let mut processed = vec![];
+        let mut ticks = 0;
+
+        while !backlog.is_empty() {
+            let batch = MapUtils::limit_by(backlog.clone(), cap, |retries| *retries);
+            assert!(batch.len() <= cap);
+            for id in batch.keys() {
+                backlog.remove(id);
+                processed.push(*id);
+            }
+            ticks += 1;
+        }
+
+        assert_eq!(ticks, 3);
+        processed.sort();
+        assert_eq!(processed, (0..25).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn should_partition_into_kept_and_dropped_entries() {
+        let map: HashMap<&str, u64> = HashMap::from([("a", 1), ("b", 5), ("c", 2), ("d", 7)]);
+
+        let (kept, dropped) = MapUtils::partition(&map, |retries| *retries < 5);
+
+        assert_eq!(kept, HashMap::from([("a", 1), ("c", 2)]));
+        assert_eq!(dropped, HashMap::from([("b", 5), ("d", 7)]));
+    }
+
+    #[test]
+    fn should_partition_empty_map() {
+        let map: HashMap<&str, u64> = HashMap::new();
+
+        let (kept, dropped) = MapUtils::partition(&map, |_| true);
+
+        assert!(kept.is_empty());
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn should_prefer_entries_with_fewer_retries() {
+        let map: HashMap<&str, u64> = HashMap::from([("a", 3), ("b", 0), ("c", 1), ("d", 2)]);
+
+        let limited = MapUtils::limit_by(map, 2, |retries| *retries);
+
+        assert_eq!(limited, HashMap::from([("b", 0), ("c", 1)]));
+    }
+}
@@ -1,10 +1,18 @@
 use crate::{
-    constants::DERIVATION_PATH,
+    constants::{
+        DERIVATION_PATH, MAX_BURN_EVENTS_PAGE_SIZE, MAX_COUPONS_PER_CALL,
+        SIGN_WITH_ECDSA_RETRY_BACKOFF, SIGN_WITH_ECDSA_RETRY_LIMIT, VERIFIED_COUPONS_CACHE_SIZE,
+    },
     events::WithdrawalEvent,
-    guard::retrieve_sol_guard,
-    logs::DEBUG,
+    guard::{retrieve_coupon_guard, retrieve_sol_guard},
+    logs::{DEBUG, INFO},
     sol_rpc_client::LedgerMemo,
-    state::{audit::process_event, event::EventType, mutate_state, read_state, State},
+    state::{
+        audit::process_event,
+        event::{EventType, WithdrawalInfo},
+        lazy_call_ecdsa_public_key, mutate_state, read_state, State, WithdrawalRequest,
+    },
+    utils::Base58Utils,
 };
 
 use candid::CandidType;
@@ -17,12 +25,25 @@ use ic_cdk::api::{
     },
 };
 use icrc_ledger_client_cdk::{CdkRuntime, ICRC1Client};
+use icrc_ledger_types::icrc1::account::{Account, Subaccount};
 use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
-use k256::ecdsa::{signature::Verifier, RecoveryId, Signature, VerifyingKey};
+use k256::ecdsa::{signature::hazmat::PrehashVerifier, RecoveryId, Signature, VerifyingKey};
 use minicbor::{Decode, Encode};
 use num_traits::ToPrimitive;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::{cell::RefCell, collections::VecDeque, future::Future, time::Duration};
+
+// (message, signature, public key, hash algorithm) of a verified coupon.
+type VerifiedCouponKey = (String, String, String, CouponHashAlgo);
+
+thread_local! {
+    // Results of recent verifications, the least recently used at the front. Changes made by
+    // queries are discarded, so the cache is filled when coupons are verified in update calls
+    // (coupon generation) and only read by the verify query. Lost on upgrade.
+    static VERIFIED_COUPONS: RefCell<VecDeque<(VerifiedCouponKey, bool)>> =
+        RefCell::new(VecDeque::with_capacity(VERIFIED_COUPONS_CACHE_SIZE));
+}
 
 #[derive(CandidType, Debug, Clone, PartialEq, Eq)]
 pub enum WithdrawError {
@@ -42,7 +63,43 @@ pub enum WithdrawError {
         err: CouponError,
     },
     UnknownBurnId(u64),
+    // the coupon of the burn is being generated by another call, retry with get_coupon(burn_id)
+    AlreadyProcessing {
+        burn_id: u64,
+    },
     RedeemedEventError(u64),
+    InvalidSolanaAddress(String),
+    InvalidSubaccount(Vec<u8>),
+    InvalidIdempotencyKey(Vec<u8>),
+    // the idempotency key was used for a withdrawal with other arguments
+    IdempotencyKeyMismatch {
+        burn_id: u64,
+    },
+    NothingToWithdraw {
+        balance: Nat,
+        fee: Nat,
+    },
+    AmountTooLow {
+        minimum: Nat,
+    },
+    // the coupon is signed in lamports, the amount after the fee has to be a multiple of granularity
+    InexactLamportAmount {
+        amount: Nat,
+        granularity: Nat,
+    },
+}
+
+impl WithdrawError {
+    // Burn id of a withdrawal whose gSOL was already burned but whose coupon could not be
+    // generated. The coupon can be retrieved later with `get_coupon(burn_id)`.
+    pub fn burn_id(&self) -> Option<u64> {
+        match self {
+            WithdrawError::SigningWithEcdsaFailed { burn_id, .. }
+            | WithdrawError::CouponError { burn_id, .. }
+            | WithdrawError::AlreadyProcessing { burn_id } => Some(*burn_id),
+            _ => None,
+        }
+    }
 }
 
 impl std::fmt::Display for WithdrawError {
@@ -76,9 +133,61 @@ impl std::fmt::Display for WithdrawError {
             WithdrawError::UnknownBurnId(burn_id) => {
                 write!(f, "Unknown burn_id {burn_id}")
             }
+            WithdrawError::AlreadyProcessing { burn_id } => {
+                write!(
+                    f,
+                    "Coupon for burn_id {burn_id} is already being generated, retry with get_coupon"
+                )
+            }
             WithdrawError::RedeemedEventError(burn_id) => {
                 write!(f, "Redeemed event does NOT hold coupon: {burn_id}")
             }
+            WithdrawError::InvalidSolanaAddress(address) => {
+                write!(f, "Invalid Solana address: {address}")
+            }
+            WithdrawError::InvalidSubaccount(subaccount) => {
+                write!(
+                    f,
+                    "Invalid subaccount {}: expected 32 bytes, got {}",
+                    hex::encode(subaccount),
+                    subaccount.len()
+                )
+            }
+            WithdrawError::InvalidIdempotencyKey(key) => {
+                write!(
+                    f,
+                    "Invalid idempotency key {}: expected 32 bytes, got {}",
+                    hex::encode(key),
+                    key.len()
+                )
+            }
+            WithdrawError::IdempotencyKeyMismatch { burn_id } => {
+                write!(
+                    f,
+                    "Idempotency key was already used for burn_id {burn_id} with other arguments"
+                )
+            }
+            WithdrawError::NothingToWithdraw { balance, fee } => {
+                write!(
+                    f,
+                    "Nothing to withdraw: balance {balance} does not cover the ledger fee {fee}"
+                )
+            }
+            WithdrawError::AmountTooLow { minimum } => {
+                write!(
+                    f,
+                    "Withdraw amount is less than minimum withdrawal amount: {minimum}"
+                )
+            }
+            WithdrawError::InexactLamportAmount {
+                amount,
+                granularity,
+            } => {
+                write!(
+                    f,
+                    "Withdraw amount after the fee {amount} is not a whole number of lamports, it has to be a multiple of {granularity}"
+                )
+            }
         }
     }
 }
@@ -89,6 +198,7 @@ pub enum CouponError {
     DeserializationError,
     RecoveryError,
     ParityRecoveryFailed { signature: String, pubkey: String },
+    MissingRecoveryId,
 }
 
 impl std::fmt::Display for CouponError {
@@ -106,40 +216,267 @@ impl std::fmt::Display for CouponError {
             CouponError::ParityRecoveryFailed { signature, pubkey } => {
                 write!(f, "Failed to recover the parity bit from a signature: {signature}, pubkey: {pubkey}")
             }
+            CouponError::MissingRecoveryId => {
+                write!(f, "Coupon does not hold a recovery id")
+            }
         }
     }
 }
 
+// Coupons are returned as stored at generation time, their recovery id included,
+// so no signature recovery or verification runs here.
 pub async fn get_withdraw_info(user: Principal) -> UserWithdrawInfo {
-    let withdrawal_redeemed_events = read_state(|s| s.withdrawal_redeemed_events.clone());
-    let mut coupons = Vec::new();
+    read_state(|s| {
+        let mut coupons = Vec::new();
+        for event in s.withdrawal_redeemed_events.values() {
+            if event.from_icp_address == user {
+                match event.get_coupon() {
+                    Some(coupon) => coupons.push(coupon.clone()),
+                    None => ic_canister_log::log!(DEBUG, "Redeemed event does NOT hold coupon"),
+                }
+            }
+        }
+
+        let burn_ids = s
+            .withdrawal_burned_events
+            .values()
+            .filter(|event| event.from_icp_address == user)
+            .map(|event| event.get_burn_id())
+            .collect();
+
+        UserWithdrawInfo { coupons, burn_ids }
+    })
+}
 
-    for (_, event) in withdrawal_redeemed_events.iter() {
-        if event.from_icp_address == user {
-            match event.get_coupon() {
-                Some(coupon) => coupons.push(coupon.clone()),
-                None => ic_canister_log::log!(DEBUG, "Redeemed event does NOT hold coupon"),
+// Burned withdrawals whose coupon was never generated, oldest first.
+pub fn get_pending_withdrawals(limit: usize) -> PendingWithdrawals {
+    read_state(|s| {
+        let mut withdrawals: Vec<PendingWithdrawal> = s
+            .withdrawal_burned_events
+            .values()
+            .filter(|event| {
+                !s.withdrawal_redeemed_events
+                    .contains_key(&event.get_burn_id())
+            })
+            .map(|event| PendingWithdrawal {
+                burn_id: event.get_burn_id(),
+                burn_timestamp: event.get_burn_timestamp(),
+            })
+            .collect();
+        withdrawals.sort_by_key(|w| w.burn_id);
+
+        let count = withdrawals.len() as u64;
+        withdrawals.truncate(limit);
+
+        PendingWithdrawals { count, withdrawals }
+    })
+}
+
+// Burned and redeemed withdrawals burned after `since_timestamp`, oldest first. Burns of the same
+// round share their timestamp, so a page may end inside such a group: with `after_burn_id` the
+// cursor is the (timestamp, burn id) of the last returned event and the rest of the group follows.
+pub fn get_burn_events_since(since_timestamp: u64, after_burn_id: Option<u64>) -> BurnEvents {
+    read_state(|s| {
+        let burned = s
+            .withdrawal_burned_events
+            .values()
+            .map(|event| (event, false));
+        let redeemed = s
+            .withdrawal_redeemed_events
+            .values()
+            .map(|event| (event, true));
+
+        let mut events: Vec<BurnEvent> = burned
+            .chain(redeemed)
+            .filter(|(event, _)| {
+                event
+                    .get_burn_timestamp()
+                    .is_some_and(|timestamp| match after_burn_id {
+                        None => timestamp > since_timestamp,
+                        Some(after) => (timestamp, event.get_burn_id()) > (since_timestamp, after),
+                    })
+            })
+            .map(|(event, redeemed)| BurnEvent {
+                withdrawal: WithdrawalInfo::from(event),
+                redeemed,
+            })
+            .collect();
+        events.sort_by_key(|e| (e.withdrawal.burn_timestamp, e.withdrawal.burn_id));
+
+        let has_more = events.len() > MAX_BURN_EVENTS_PAGE_SIZE;
+        events.truncate(MAX_BURN_EVENTS_PAGE_SIZE);
+
+        BurnEvents { events, has_more }
+    })
+}
+
+// Burned or redeemed withdrawal with the given burn id.
+pub fn get_withdrawal_by_burn_id(burn_id: u64) -> Option<BurnEvent> {
+    read_state(|s| {
+        if let Some(event) = s.withdrawal_redeemed_events.get(&burn_id) {
+            return Some(BurnEvent {
+                withdrawal: WithdrawalInfo::from(event),
+                redeemed: true,
+            });
+        }
+        s.withdrawal_burned_events
+            .get(&burn_id)
+            .map(|event| BurnEvent {
+                withdrawal: WithdrawalInfo::from(event),
+                redeemed: false,
+            })
+    })
+}
+
+// Withdrawals to the given Solana address, oldest burn first, at most MAX_BURN_EVENTS_PAGE_SIZE.
+pub fn find_withdrawals_by_sol_address(address: &str) -> BurnEvents {
+    read_state(|s| {
+        let burned = s
+            .withdrawal_burned_events
+            .values()
+            .map(|event| (event, false));
+        let redeemed = s
+            .withdrawal_redeemed_events
+            .values()
+            .map(|event| (event, true));
+
+        let mut events: Vec<BurnEvent> = burned
+            .chain(redeemed)
+            .filter(|(event, _)| event.to_sol_address == address)
+            .map(|(event, redeemed)| BurnEvent {
+                withdrawal: WithdrawalInfo::from(event),
+                redeemed,
+            })
+            .collect();
+        events.sort_by_key(|e| e.withdrawal.burn_id);
+
+        let has_more = events.len() > MAX_BURN_EVENTS_PAGE_SIZE;
+        events.truncate(MAX_BURN_EVENTS_PAGE_SIZE);
+
+        BurnEvents { events, has_more }
+    })
+}
+
+// The ledger fee cannot be queried while the config is validated (init is synchronous),
+// so it is checked once after install/upgrade and a misconfiguration is reported in the logs.
+pub async fn check_minimum_withdrawal_amount() {
+    let ledger_canister_id = read_state(|s| s.ledger_id);
+    let client = ICRC1Client {
+        runtime: CdkRuntime,
+        ledger_canister_id,
+    };
+
+    match client.fee().await {
+        Ok(fee) => {
+            if read_state(|s| s.is_minimum_withdrawal_below_ledger_fee(&fee)) {
+                let minimum = read_state(|s| s.minimum_withdrawal_amount.clone());
+                ic_canister_log::log!(
+                    INFO,
+                    "\nALERT: minimum_withdrawal_amount {minimum} is lower than the ledger fee {fee}, withdrawals of such amounts fail"
+                );
             }
         }
+        Err((code, msg)) => {
+            ic_canister_log::log!(
+                INFO,
+                "\nFailed to query the fee of ledger {ledger_canister_id}: {code} {msg}"
+            );
+        }
+    }
+}
+
+/// Runs all checks a withdrawal has to pass before any gSOL is burned.
+pub fn validate_withdrawal(to: &str, amount: &Nat) -> Result<(), WithdrawError> {
+    // Solana addresses are base58 encoded 32 byte public keys
+    match Base58Utils::decode(to) {
+        Some(bytes) if bytes.len() == 32 => {}
+        _ => return Err(WithdrawError::InvalidSolanaAddress(to.to_string())),
     }
 
-    let withdrawal_burned_events = read_state(|s| s.withdrawal_burned_events.clone());
-    let mut burn_ids = Vec::new();
+    let minimum = read_state(|s| s.minimum_withdrawal_amount.clone());
+    if amount.0 < minimum {
+        return Err(WithdrawError::AmountTooLow {
+            minimum: Nat::from(minimum),
+        });
+    }
 
-    withdrawal_burned_events.iter().for_each(|(_, event)| {
-        if event.from_icp_address == user {
-            burn_ids.push(event.get_burn_id());
-        }
-    });
+    // the config guarantees fee < minimum, checked again as the coupon amount must stay positive
+    let fee = read_state(|s| s.withdrawal_fee.clone());
+    if amount.0 <= fee {
+        return Err(WithdrawError::AmountTooLow {
+            minimum: Nat::from(fee + 1u8),
+        });
+    }
+
+    // a remainder below one lamport could never be released on Solana
+    let coupon_amount = Nat(&amount.0 - fee);
+    if read_state(|s| s.gsol_to_lamports(&coupon_amount)).is_none() {
+        return Err(WithdrawError::InexactLamportAmount {
+            amount: coupon_amount,
+            granularity: Nat::from(read_state(|s| s.lamport_granularity())),
+        });
+    }
+
+    Ok(())
+}
+
+/// Validates a withdrawal and returns the unsigned coupon payload without burning or signing.
+/// `burn_id` and `burn_timestamp` are predictions and `icp_burn_block_index` is unknown (0)
+/// until the ledger executes the burn.
+pub fn simulate_withdraw(
+    from: Principal,
+    to: String,
+    amount: Nat,
+) -> Result<WithdrawalEventWithoutCbor, WithdrawError> {
+    validate_withdrawal(&to, &amount)?;
+
+    // the coupon is signed for the lamports left after the withdrawal fee
+    let lamports = read_state(|s| s.gsol_to_lamports(&Nat(amount.0 - &s.withdrawal_fee)))
+        .expect("BUG: validated withdrawal amount does not convert to lamports");
 
-    UserWithdrawInfo { coupons, burn_ids }
+    Ok(WithdrawalEventWithoutCbor {
+        from_icp_address: from,
+        to_sol_address: to,
+        amount: lamports.to_string(),
+        burn_id: read_state(|s| s.burn_id_counter),
+        burn_timestamp: ic_cdk::api::time(),
+        icp_burn_block_index: 0,
+    })
+}
+
+// ICRC-1 subaccounts are exactly 32 bytes long.
+pub fn validate_subaccount(
+    subaccount: Option<Vec<u8>>,
+) -> Result<Option<Subaccount>, WithdrawError> {
+    subaccount
+        .map(|bytes| {
+            Subaccount::try_from(bytes.as_slice())
+                .map_err(|_| WithdrawError::InvalidSubaccount(bytes))
+        })
+        .transpose()
+}
+
+pub fn validate_idempotency_key(key: Option<Vec<u8>>) -> Result<Option<[u8; 32]>, WithdrawError> {
+    key.map(|bytes| {
+        <[u8; 32]>::try_from(bytes.as_slice())
+            .map_err(|_| WithdrawError::InvalidIdempotencyKey(bytes))
+    })
+    .transpose()
 }
 
 pub async fn withdraw_gsol(
     from: Principal,
+    from_subaccount: Option<Vec<u8>>,
     to: String,
     amount: Nat,
+    idempotency_key: Option<Vec<u8>>,
 ) -> Result<Coupon, WithdrawError> {
+    validate_withdrawal(&to, &amount)?;
+    let from_subaccount = validate_subaccount(from_subaccount)?;
+    let idempotency_key = validate_idempotency_key(idempotency_key)?;
+    // make sure the signing key is available before anything is burned
+    lazy_call_ecdsa_public_key().await;
+
     let _guard = retrieve_sol_guard(from).unwrap_or_else(|e| {
         ic_cdk::trap(&format!(
             "Failed retrieving guard for principal {}: {:?}",
@@ -147,43 +484,139 @@ pub async fn withdraw_gsol(
         ))
     });
 
-    let mut event = burn_gsol(&from, &to, amount).await.map_err(|err| err)?;
+    // a retried call whose first attempt already burned gets the coupon of that burn, the guard
+    // above makes a retry racing the first attempt fail instead
+    let request = WithdrawalRequest {
+        to: to.clone(),
+        amount: amount.clone(),
+        from_subaccount,
+    };
+    if let Some(key) = idempotency_key {
+        if let Some(burn_id) =
+            read_state(|s| s.idempotent_burn_id(&from, &key, &request, ic_cdk::api::time()))?
+        {
+            return get_coupon(burn_id).await;
+        }
+    }
+
+    let mut event = burn_gsol(&from, from_subaccount, &to, amount)
+        .await
+        .map_err(|err| err)?;
+
+    if let Some(key) = idempotency_key {
+        mutate_state(|s| {
+            s.record_idempotency_key(from, key, request, event.get_burn_id(), ic_cdk::api::time())
+        });
+    }
+
+    let burn_id = event.get_burn_id();
+    let _coupon_guard =
+        retrieve_coupon_guard(burn_id).map_err(|_| WithdrawError::AlreadyProcessing { burn_id })?;
     let coupon = generate_coupon(&mut event).await.map_err(|err| err)?;
 
     Ok(coupon)
 }
 
-pub async fn get_coupon(from: Principal, burn_id: u64) -> Result<Coupon, WithdrawError> {
-    let _guard = retrieve_sol_guard(from).unwrap_or_else(|e| {
+// Withdraws the whole gSOL balance of the default account, minus the ledger fee.
+pub async fn withdraw_all_gsol(from: Principal, to: String) -> Result<Coupon, WithdrawError> {
+    let ledger_canister_id = read_state(|s| s.ledger_id);
+    let client = ICRC1Client {
+        runtime: CdkRuntime,
+        ledger_canister_id,
+    };
+    let ledger_error = |err: (i32, String)| WithdrawError::SendingMessageToLedgerFailed {
+        ledger_id: ledger_canister_id.to_string(),
+        code: err.0,
+        msg: err.1,
+    };
+
+    let balance = client.balance_of(from.into()).await.map_err(ledger_error)?;
+    let fee = client.fee().await.map_err(ledger_error)?;
+
+    let amount = withdraw_all_amount(balance, fee)?;
+    withdraw_gsol(from, None, to, amount, None).await
+}
+
+// Amount withdrawn by `withdraw_all_gsol` from the given balance and ledger fee.
+fn withdraw_all_amount(balance: Nat, fee: Nat) -> Result<Nat, WithdrawError> {
+    if balance <= fee {
+        return Err(WithdrawError::NothingToWithdraw { balance, fee });
+    }
+
+    let mut amount = Nat(&balance.0 - &fee.0);
+    // the dust below one lamport stays on the account, the coupon could not release it
+    let (withdrawal_fee, granularity) =
+        read_state(|s| (s.withdrawal_fee.clone(), s.lamport_granularity()));
+    if amount.0 > withdrawal_fee {
+        let dust = (&amount.0 - withdrawal_fee) % granularity;
+        amount.0 -= dust;
+    }
+    Ok(amount)
+}
+
+pub async fn get_coupon(burn_id: u64) -> Result<Coupon, WithdrawError> {
+    // Fast path: once signed, the coupon is stored on the redeemed event,
+    // so polling for it is a cheap lookup that never signs again.
+    if let Some(coupon) = get_redeemed_coupon(burn_id) {
+        return coupon;
+    }
+
+    // Coupon regeneration is serialized per burn_id, so concurrent calls for the same
+    // burn_id do not sign twice, while distinct burn_ids are processed in parallel.
+    let _guard =
+        retrieve_coupon_guard(burn_id).map_err(|_| WithdrawError::AlreadyProcessing { burn_id })?;
+
+    match read_state(|s| s.withdrawal_burned_events.get(&burn_id).cloned()) {
+        Some(mut event) => generate_coupon(&mut event).await,
+        None => Err(WithdrawError::UnknownBurnId(burn_id)),
+    }
+}
+
+// Signs the coupons of the user's burned but not yet redeemed withdrawals, oldest first.
+// At most MAX_COUPONS_PER_CALL are processed, the rest is left for the next call.
+pub async fn generate_all_coupons(user: Principal) -> Vec<Result<Coupon, WithdrawError>> {
+    let _guard = retrieve_sol_guard(user).unwrap_or_else(|e| {
         ic_cdk::trap(&format!(
             "Failed retrieving guard for principal {}: {:?}",
-            from, e
+            user, e
         ))
     });
 
-    let events = read_state(|s| s.withdrawal_redeemed_events.clone());
+    let burn_ids = read_state(|s| pending_coupon_burn_ids(s, user));
 
-    match events.get(&burn_id) {
-        Some(redeemed_event) => match redeemed_event.get_coupon() {
-            Some(coupon) => Ok(coupon.clone()),
-            None => Err(WithdrawError::RedeemedEventError(burn_id)),
-        },
-        None => {
-            let burned_events = read_state(|s| s.withdrawal_burned_events.clone());
-            match burned_events.get(&burn_id) {
-                Some(burned_event) => {
-                    let mut event = burned_event.clone();
-                    let coupon = generate_coupon(&mut event).await.map_err(|err| err)?;
-                    Ok(coupon)
-                }
-                None => return Err(WithdrawError::UnknownBurnId(burn_id)),
-            }
-        }
+    let mut coupons = Vec::with_capacity(burn_ids.len());
+    for burn_id in burn_ids {
+        coupons.push(get_coupon(burn_id).await);
     }
+    coupons
+}
+
+// Oldest burns of the user still waiting for their coupon, at most MAX_COUPONS_PER_CALL.
+fn pending_coupon_burn_ids(state: &State, user: Principal) -> Vec<u64> {
+    state
+        .withdrawal_burned_events
+        .values()
+        .filter(|event| event.from_icp_address == user)
+        .map(|event| event.get_burn_id())
+        .filter(|burn_id| !state.withdrawal_redeemed_events.contains_key(burn_id))
+        .take(MAX_COUPONS_PER_CALL)
+        .collect()
+}
+
+fn get_redeemed_coupon(burn_id: u64) -> Option<Result<Coupon, WithdrawError>> {
+    read_state(|s| {
+        s.withdrawal_redeemed_events.get(&burn_id).map(|event| {
+            event
+                .get_coupon()
+                .cloned()
+                .ok_or(WithdrawError::RedeemedEventError(burn_id))
+        })
+    })
 }
 
 async fn burn_gsol(
     from: &Principal,
+    from_subaccount: Option<Subaccount>,
     to: &String,
     amount: Nat,
 ) -> Result<WithdrawalEvent, WithdrawError> {
@@ -192,6 +625,7 @@ async fn burn_gsol(
         from.clone(),
         to.clone(),
         amount,
+        read_state(|s| Nat::from(s.withdrawal_fee.clone())),
     );
 
     let ledger_canister_id = read_state(|s| s.ledger_id);
@@ -200,15 +634,7 @@ async fn burn_gsol(
         ledger_canister_id,
     };
 
-    let args = TransferFromArgs {
-        spender_subaccount: None,
-        from: event.from_icp_address.into(),
-        to: ic_cdk::id().into(),
-        amount: event.amount.clone(),
-        fee: None,
-        created_at_time: Some(ic_cdk::api::time()),
-        memo: Some(LedgerMemo(event.get_burn_id()).into()),
-    };
+    let args = burn_args(&event, from_subaccount, ic_cdk::id(), ic_cdk::api::time());
 
     match client.transfer_from(args).await {
         Ok(Ok(block_index)) => {
@@ -233,6 +659,27 @@ async fn burn_gsol(
     }
 }
 
+// Moves the withdrawn gSOL from the (sub)account of the user to the minter, which burns it.
+fn burn_args(
+    event: &WithdrawalEvent,
+    from_subaccount: Option<Subaccount>,
+    minter_id: Principal,
+    now: u64,
+) -> TransferFromArgs {
+    TransferFromArgs {
+        spender_subaccount: None,
+        from: Account {
+            owner: event.from_icp_address,
+            subaccount: from_subaccount,
+        },
+        to: minter_id.into(),
+        amount: event.amount.clone(),
+        fee: None,
+        created_at_time: Some(now),
+        memo: Some(LedgerMemo::withdrawal(event.get_burn_id()).into()),
+    }
+}
+
 async fn generate_coupon(event: &mut WithdrawalEvent) -> Result<Coupon, WithdrawError> {
     match event.to_coupon().await {
         Ok(coupon) => {
@@ -290,6 +737,62 @@ pub struct Coupon {
     pub icp_public_key_hex: String,
     #[n(4)]
     pub recovery_id: Option<u8>,
+    // coupons signed before the hash algorithm was configurable use SHA-256
+    #[n(5)]
+    pub hash_algo: Option<CouponHashAlgo>,
+}
+
+/// Hash function applied to the coupon message before it is signed.
+#[derive(
+    CandidType,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Debug,
+    Encode,
+    Decode,
+    Deserialize,
+    Serialize,
+)]
+pub enum CouponHashAlgo {
+    #[default]
+    #[n(0)]
+    Sha256,
+    // for EVM-style verifiers
+    #[n(1)]
+    Keccak256,
+}
+
+impl CouponHashAlgo {
+    pub fn hash(&self, message: &[u8]) -> Vec<u8> {
+        match self {
+            CouponHashAlgo::Sha256 => Sha256::digest(message).to_vec(),
+            CouponHashAlgo::Keccak256 => {
+                use tiny_keccak::{Hasher, Keccak};
+
+                let mut hasher = Keccak::v256();
+                hasher.update(message);
+                let mut hash = [0u8; 32];
+                hasher.finalize(&mut hash);
+                hash.to_vec()
+            }
+        }
+    }
+}
+
+// Returns the recovery id under which the signature recovers to `key`. The message is hashed
+// once by the caller; the second id is only tried if the first one does not match, and a
+// failed recovery counts as a mismatch instead of aborting the search.
+fn recover_parity(message_hash: &[u8], signature: &Signature, key: &VerifyingKey) -> Option<u8> {
+    [0u8, 1].into_iter().find(|&parity| {
+        let rec_id = RecoveryId::try_from(parity).unwrap();
+        VerifyingKey::recover_from_prehash(message_hash, signature, rec_id)
+            .is_ok_and(|recovered_key| recovered_key.eq(key))
+    })
 }
 
 impl Coupon {
@@ -299,6 +802,7 @@ impl Coupon {
         message_hash: String,
         signature_hex: String,
         icp_public_key_hex: String,
+        hash_algo: CouponHashAlgo,
     ) -> Self {
         Self {
             message,
@@ -306,9 +810,14 @@ impl Coupon {
             signature_hex,
             icp_public_key_hex,
             recovery_id: None,
+            hash_algo: Some(hash_algo),
         }
     }
 
+    pub fn hash_algo(&self) -> CouponHashAlgo {
+        self.hash_algo.unwrap_or_default()
+    }
+
     pub fn y_parity(&mut self) -> Result<u8, CouponError> {
         let signature_bytes =
             hex::decode(&self.signature_hex).map_err(|_| CouponError::HexDecodingError)?;
@@ -319,45 +828,81 @@ impl Coupon {
         let orig_key = VerifyingKey::from_sec1_bytes(&pubkey_bytes)
             .map_err(|_| CouponError::DeserializationError)?;
 
-        let message_bytes = self.message.as_bytes();
-
-        for parity in [0u8, 1] {
-            let rec_id = RecoveryId::try_from(parity).unwrap();
-            let recovered_key = VerifyingKey::recover_from_msg(&message_bytes, &signature, rec_id)
-                .map_err(|_| CouponError::RecoveryError)?;
+        let message_hash = self.hash_algo().hash(self.message.as_bytes());
 
-            if recovered_key.eq(&orig_key) {
+        match recover_parity(&message_hash, &signature, &orig_key) {
+            Some(parity) => {
                 self.recovery_id = Some(parity);
-                return Ok(parity);
+                Ok(parity)
             }
+            None => Err(CouponError::ParityRecoveryFailed {
+                signature: self.signature_hex.to_string(),
+                pubkey: self.icp_public_key_hex.to_string(),
+            }),
         }
-
-        Err(CouponError::ParityRecoveryFailed {
-            signature: self.signature_hex.to_string(),
-            pubkey: self.icp_public_key_hex.to_string(),
-        })
     }
 
     pub fn verify(&self) -> Result<bool, CouponError> {
+        // Coupons are redeemed on Solana with an ecrecover-style check,
+        // so a coupon without the recovery id must never be accepted.
+        if self.recovery_id.is_none() {
+            return Err(CouponError::MissingRecoveryId);
+        }
+
+        let key = (
+            self.message.clone(),
+            self.signature_hex.clone(),
+            self.icp_public_key_hex.clone(),
+            self.hash_algo(),
+        );
+        let cached = VERIFIED_COUPONS.with(|cache| {
+            let mut cache = cache.borrow_mut();
+            let position = cache.iter().position(|(k, _)| *k == key)?;
+            let entry = cache.remove(position)?;
+            let verified = entry.1;
+            cache.push_back(entry);
+            Some(verified)
+        });
+        if let Some(verified) = cached {
+            return Ok(verified);
+        }
+
+        let verified = self.verify_signature()?;
+        VERIFIED_COUPONS.with(|cache| {
+            let mut cache = cache.borrow_mut();
+            if cache.len() >= VERIFIED_COUPONS_CACHE_SIZE {
+                cache.pop_front();
+            }
+            cache.push_back((key, verified));
+        });
+        Ok(verified)
+    }
+
+    fn verify_signature(&self) -> Result<bool, CouponError> {
         let signature_bytes =
             hex::decode(&self.signature_hex).map_err(|_| CouponError::HexDecodingError)?;
         let pubkey_bytes =
             hex::decode(&self.icp_public_key_hex).map_err(|_| CouponError::HexDecodingError)?;
-        let message_bytes = self.message.as_bytes();
+        let message_hash = self.hash_algo().hash(self.message.as_bytes());
 
         let signature = Signature::try_from(signature_bytes.as_slice())
             .map_err(|_| CouponError::DeserializationError)?;
 
         Ok(VerifyingKey::from_sec1_bytes(&pubkey_bytes)
             .map_err(|_| CouponError::DeserializationError)?
-            .verify(message_bytes, &signature)
+            .verify_prehash(&message_hash, &signature)
             .is_ok())
     }
 }
 
 impl WithdrawalEvent {
     pub async fn to_coupon(&self) -> Result<Coupon, WithdrawError> {
-        match self.sign_with_ecdsa().await {
+        // the key is fetched on demand if the post-install timer has not run yet
+        lazy_call_ecdsa_public_key().await;
+
+        let hash_algo = read_state(|s| s.coupon_hash_algo);
+
+        match self.sign_with_ecdsa(hash_algo).await {
             Ok((serialized_coupon, message_hash, signature_hex)) => {
                 let icp_public_key_hex = read_state(|s| s.uncompressed_public_key());
 
@@ -366,25 +911,23 @@ impl WithdrawalEvent {
                     message_hash,
                     signature_hex,
                     icp_public_key_hex,
+                    hash_algo,
                 );
 
-                let res = match response.y_parity() {
-                    Ok(_) => Ok(response),
-                    Err(err) => {
-                        return Err(WithdrawError::CouponError {
-                            burn_id: self.get_burn_id(),
-                            err,
-                        })
-                    }
-                };
-
-                _ = res
-                    .clone()
-                    .unwrap()
+                // The recovery id is computed once here and stored on the coupon,
+                // so every endpoint returning this coupon returns it populated.
+                if let Err(err) = response.y_parity() {
+                    return Err(WithdrawError::CouponError {
+                        burn_id: self.get_burn_id(),
+                        err,
+                    });
+                }
+
+                _ = response
                     .verify()
                     .map(|a| ic_canister_log::log!(DEBUG, "{a}"));
 
-                res
+                Ok(response)
             }
             Err((code, msg)) => Err(WithdrawError::SigningWithEcdsaFailed {
                 burn_id: self.get_burn_id(),
@@ -394,45 +937,186 @@ impl WithdrawalEvent {
         }
     }
 
-    async fn sign_with_ecdsa(&self) -> Result<(String, String, String), (RejectionCode, String)> {
-        // Serialize the coupon
-        let serialized_coupon: String = serde_json::to_string(&WithdrawalEventWithoutCbor {
-            from_icp_address: self.from_icp_address.clone(),
-            to_sol_address: self.to_sol_address.clone(),
-            amount: self.amount.to_string(),
-            burn_id: self.get_burn_id(),
-            burn_timestamp: self.get_burn_timestamp().unwrap(),
-            icp_burn_block_index: self.get_icp_burn_block_index().unwrap(),
-        })
-        .unwrap();
+    // Serialized coupon message and its hash, exactly as they are signed.
+    fn coupon_message(&self, hash_algo: CouponHashAlgo) -> (String, Vec<u8>) {
+        // The message is the canonical JSON below, built by hand so the field order and format
+        // never depend on struct declarations. The Solana contract reconstructs these exact bytes:
+        // {"from_icp_address":"<principal>","to_sol_address":"<address>","amount":"<amount>",
+        //  "burn_id":<u64>,"burn_timestamp":<u64>,"icp_burn_block_index":<u64>}
+        // (no whitespace, `amount` is the candid Nat display, e.g. "100_000", of the lamports
+        //  released on Solana: the burned amount minus the withdrawal fee, converted from the
+        //  ledger's decimals)
+        let json_string = |value: String| serde_json::Value::String(value).to_string();
+        // token_decimals cannot change after install and burned amounts are validated to convert
+        let lamports = read_state(|s| s.gsol_to_lamports(&self.coupon_amount()))
+            .expect("BUG: coupon amount is not a whole number of lamports");
+
+        let serialized_coupon = format!(
+            "{{\"from_icp_address\":{},\"to_sol_address\":{},\"amount\":{},\"burn_id\":{},\"burn_timestamp\":{},\"icp_burn_block_index\":{}}}",
+            json_string(self.from_icp_address.to_text()),
+            json_string(self.to_sol_address.clone()),
+            json_string(lamports.to_string()),
+            self.get_burn_id(),
+            self.get_burn_timestamp().unwrap(),
+            self.get_icp_burn_block_index().unwrap(),
+        );
+
+        let hashed_coupon = hash_algo.hash(serialized_coupon.as_bytes());
+
+        (serialized_coupon, hashed_coupon)
+    }
+
+    async fn sign_with_ecdsa(
+        &self,
+        hash_algo: CouponHashAlgo,
+    ) -> Result<(String, String, String), (RejectionCode, String)> {
+        let (serialized_coupon, hashed_coupon) = self.coupon_message(hash_algo);
 
         ic_canister_log::log!(DEBUG, "{serialized_coupon}");
 
-        // Hash the serialized coupon using SHA-256
-        let mut hasher = Sha256::new();
-        hasher.update(serialized_coupon.clone());
-        let hashed_coupon = hasher.finalize().to_vec();
-
-        let args = SignWithEcdsaArgument {
-            message_hash: hashed_coupon.clone(),
-            derivation_path: DERIVATION_PATH.into_iter().map(|x| x.to_vec()).collect(),
-            key_id: EcdsaKeyId {
-                curve: EcdsaCurve::Secp256k1,
-                name: read_state(|s| s.ecdsa_key_name.clone()),
+        let signature = sign_message_hash(hashed_coupon.clone()).await?;
+
+        Ok((
+            serialized_coupon,
+            hex::encode(hashed_coupon),
+            hex::encode(signature),
+        ))
+    }
+}
+
+// Signs the hash with the configured threshold ECDSA key.
+// Transient rejections (e.g. a busy subnet) are retried with an exponential backoff, up to
+// SIGN_WITH_ECDSA_RETRY_LIMIT times. Other rejections, e.g. missing cycles or an unknown key,
+// fail immediately.
+async fn sign_message_hash(message_hash: Vec<u8>) -> Result<Vec<u8>, (RejectionCode, String)> {
+    let response: Result<(SignWithEcdsaResponse,), (RejectionCode, String)> =
+        retry_transient_rejections(
+            || {
+                sign_with_ecdsa(SignWithEcdsaArgument {
+                    message_hash: message_hash.clone(),
+                    derivation_path: DERIVATION_PATH.into_iter().map(|x| x.to_vec()).collect(),
+                    key_id: EcdsaKeyId {
+                        curve: EcdsaCurve::Secp256k1,
+                        name: read_state(|s| s.ecdsa_key_name.clone()),
+                    },
+                })
             },
-        };
-        let response: Result<(SignWithEcdsaResponse,), (RejectionCode, String)> =
-            sign_with_ecdsa(args).await;
-
-        match response {
-            Ok(res) => Ok((
-                serialized_coupon,
-                hex::encode(hashed_coupon),
-                hex::encode(&res.0.signature),
-            )),
-            Err((code, msg)) => Err((code, msg)),
+            sleep,
+        )
+        .await;
+
+    response.map(|(res,)| res.signature)
+}
+
+// Repeats `call` while it is rejected with SysTransient, at most SIGN_WITH_ECDSA_RETRY_LIMIT times,
+// waiting SIGN_WITH_ECDSA_RETRY_BACKOFF before the first repetition and twice as long before each
+// further one.
+async fn retry_transient_rejections<T, F, Fut, B, BFut>(
+    mut call: F,
+    mut backoff: B,
+) -> Result<T, (RejectionCode, String)>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, (RejectionCode, String)>>,
+    B: FnMut(Duration) -> BFut,
+    BFut: Future<Output = ()>,
+{
+    let mut retries = 0;
+    loop {
+        match call().await {
+            Ok(response) => return Ok(response),
+            Err((RejectionCode::SysTransient, msg)) if retries < SIGN_WITH_ECDSA_RETRY_LIMIT => {
+                let delay = SIGN_WITH_ECDSA_RETRY_BACKOFF * 2u32.pow(retries as u32);
+                retries += 1;
+                ic_canister_log::log!(
+                    DEBUG,
+                    "\nTransient signing failure, retry {retries}/{SIGN_WITH_ECDSA_RETRY_LIMIT} in {delay:?}: {msg}"
+                );
+                backoff(delay).await;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+// Resumes the awaiting call once a one-shot timer fired after `delay`.
+async fn sleep(delay: Duration) {
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    ic_cdk_timers::set_timer(delay, move || {
+        let _ = sender.send(());
+    });
+    let _ = receiver.await;
+}
+
+const SELF_TEST_MESSAGE: &str = "galactic-bridge minter self test";
+
+#[derive(CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct SelfTestReport {
+    pub signed: bool,
+    pub parity_recovered: bool,
+    pub verified: bool,
+    // error of the first failed step
+    pub error: Option<String>,
+}
+
+// Runs the coupon signing pipeline (sign, recover parity, verify) on a fixed dummy message
+// with the configured key, so misconfiguration shows up before the first real withdrawal.
+pub async fn self_test() -> SelfTestReport {
+    lazy_call_ecdsa_public_key().await;
+
+    let hash_algo = read_state(|s| s.coupon_hash_algo);
+    let public_key_hex = read_state(|s| s.uncompressed_public_key());
+    run_self_test(hash_algo, public_key_hex, sign_message_hash).await
+}
+
+// Signs the self test message with `sign` and checks the result the way coupons are checked.
+async fn run_self_test<F, Fut>(
+    hash_algo: CouponHashAlgo,
+    public_key_hex: String,
+    sign: F,
+) -> SelfTestReport
+where
+    F: FnOnce(Vec<u8>) -> Fut,
+    Fut: Future<Output = Result<Vec<u8>, (RejectionCode, String)>>,
+{
+    let mut report = SelfTestReport {
+        signed: false,
+        parity_recovered: false,
+        verified: false,
+        error: None,
+    };
+
+    let message_hash = hash_algo.hash(SELF_TEST_MESSAGE.as_bytes());
+    let signature = match sign(message_hash.clone()).await {
+        Ok(signature) => signature,
+        Err((code, msg)) => {
+            report.error = Some(format!("signing failed: {code:?} {msg}"));
+            return report;
         }
+    };
+    report.signed = true;
+
+    let mut coupon = Coupon::new(
+        SELF_TEST_MESSAGE.to_string(),
+        hex::encode(message_hash),
+        hex::encode(signature),
+        public_key_hex,
+        hash_algo,
+    );
+
+    if let Err(err) = coupon.y_parity() {
+        report.error = Some(format!("parity recovery failed: {err}"));
+        return report;
+    }
+    report.parity_recovered = true;
+
+    match coupon.verify() {
+        Ok(true) => report.verified = true,
+        Ok(false) => report.error = Some("signature verification failed".to_string()),
+        Err(err) => report.error = Some(format!("signature verification failed: {err}")),
     }
+
+    report
 }
 
 #[derive(
@@ -445,7 +1129,108 @@ pub struct UserWithdrawInfo {
     pub burn_ids: Vec<u64>,
 }
 
-#[derive(Serialize)]
+#[derive(CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct PendingWithdrawal {
+    pub burn_id: u64,
+    pub burn_timestamp: Option<u64>,
+}
+
+#[derive(CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct PendingWithdrawals {
+    // total number of pending withdrawals, regardless of the limit
+    pub count: u64,
+    pub withdrawals: Vec<PendingWithdrawal>,
+}
+
+#[derive(CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct BurnEvent {
+    pub withdrawal: WithdrawalInfo,
+    // the coupon was generated
+    pub redeemed: bool,
+}
+
+#[derive(CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct BurnEvents {
+    pub events: Vec<BurnEvent>,
+    // more events than the page size were burned after the timestamp,
+    // continue from the burn_timestamp of the last returned event
+    pub has_more: bool,
+}
+
+#[derive(CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct CouponSignature {
+    // hex encoded 32 byte halves of the signature
+    pub r: String,
+    pub s: String,
+    // recovery id (y parity)
+    pub v: u8,
+    pub message_hash: String,
+    // uncompressed public key in hex format
+    pub public_key: String,
+}
+
+impl Coupon {
+    // Splits the signature into the (r, s, v) form used by the Solana redemption instruction.
+    pub fn split_signature(&self) -> Result<CouponSignature, CouponError> {
+        let v = self.recovery_id.ok_or(CouponError::MissingRecoveryId)?;
+        let signature =
+            hex::decode(&self.signature_hex).map_err(|_| CouponError::HexDecodingError)?;
+        if signature.len() != 64 {
+            return Err(CouponError::DeserializationError);
+        }
+
+        Ok(CouponSignature {
+            r: hex::encode(&signature[..32]),
+            s: hex::encode(&signature[32..]),
+            v,
+            message_hash: self.message_hash.clone(),
+            public_key: self.icp_public_key_hex.clone(),
+        })
+    }
+}
+
+// Returns the signature of an already generated coupon split into (r, s, v).
+pub fn get_coupon_signature(burn_id: u64) -> Result<CouponSignature, WithdrawError> {
+    let coupon = match get_redeemed_coupon(burn_id) {
+        Some(coupon) => coupon?,
+        None => return Err(WithdrawError::UnknownBurnId(burn_id)),
+    };
+
+    coupon
+        .split_signature()
+        .map_err(|err| WithdrawError::CouponError { burn_id, err })
+}
+
+#[derive(CandidType, Clone, Debug, PartialEq, Eq)]
+pub struct CouponMessage {
+    pub message: String,
+    pub message_hash: String,
+}
+
+// Recomputes the signed coupon message of a burned or redeemed withdrawal without signing it.
+pub fn get_coupon_message(burn_id: u64) -> Result<CouponMessage, WithdrawError> {
+    let event = read_state(|s| {
+        s.withdrawal_redeemed_events
+            .get(&burn_id)
+            .or_else(|| s.withdrawal_burned_events.get(&burn_id))
+            .cloned()
+    })
+    .ok_or(WithdrawError::UnknownBurnId(burn_id))?;
+
+    // a signed coupon keeps the algorithm it was hashed with
+    let hash_algo = match event.get_coupon() {
+        Some(coupon) => coupon.hash_algo(),
+        None => read_state(|s| s.coupon_hash_algo),
+    };
+    let (message, message_hash) = event.coupon_message(hash_algo);
+
+    Ok(CouponMessage {
+        message,
+        message_hash: hex::encode(message_hash),
+    })
+}
+
+#[derive(CandidType, Serialize)]
 pub struct WithdrawalEventWithoutCbor {
     pub from_icp_address: Principal,
     pub to_sol_address: String,
@@ -454,3 +1239,766 @@ pub struct WithdrawalEventWithoutCbor {
     pub burn_timestamp: u64,
     pub icp_burn_block_index: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::{self, SOLANA_ADDRESS};
+
+    fn set_state(token_decimals: u8, minimum: u128, fee: u128) {
+        let mut arg = test_fixtures::init_arg();
+        arg.token_decimals = Some(token_decimals);
+        arg.minimum_withdrawal_amount = Nat::from(minimum);
+        arg.withdrawal_fee = Some(Nat::from(fee));
+        test_fixtures::set_state(State::try_from(arg).unwrap());
+    }
+
+    fn burned_event(amount: Nat) -> WithdrawalEvent {
+        let fee = read_state(|s| Nat::from(s.withdrawal_fee.clone()));
+        let mut event = WithdrawalEvent::new(
+            0,
+            test_fixtures::principal(1),
+            SOLANA_ADDRESS.to_string(),
+            amount,
+            fee,
+        );
+        event.update_after_burn(1_711_616_761_296_437_000, 106);
+        event
+    }
+
+    fn burned_at(burn_id: u64, timestamp: u64) -> WithdrawalEvent {
+        let mut event = WithdrawalEvent::new(
+            burn_id,
+            test_fixtures::principal(1),
+            SOLANA_ADDRESS.to_string(),
+            Nat::from(100_000u64),
+            Nat::from(0u64),
+        );
+        event.update_after_burn(timestamp, burn_id);
+        event
+    }
+
+    // Coupon as returned by `generate_coupon`, the signature is not checked.
+    fn coupon(event: &WithdrawalEvent) -> Coupon {
+        let (message, message_hash) = event.coupon_message(CouponHashAlgo::Sha256);
+        Coupon {
+            message,
+            message_hash: hex::encode(message_hash),
+            signature_hex: "ab".repeat(64),
+            icp_public_key_hex: "cd".repeat(33),
+            recovery_id: Some(1),
+            hash_algo: Some(CouponHashAlgo::Sha256),
+        }
+    }
+
+    fn coupon_lamports(event: &WithdrawalEvent) -> String {
+        let (message, _) = event.coupon_message(CouponHashAlgo::Sha256);
+        let message: serde_json::Value = serde_json::from_str(&message).unwrap();
+        message["amount"].as_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn should_sign_golden_coupon_message() {
+        set_state(9, 1_000, 0);
+        let event = burned_event(Nat::from(1_500_000_000u64));
+
+        let (message, message_hash) = event.coupon_message(CouponHashAlgo::Sha256);
+
+        // the Solana contract reconstructs these exact bytes, any change breaks redemption
+        assert_eq!(
+            message,
+            r#"{"from_icp_address":"wmzac-nabae-aqcai-baeaq-caiba-eaqca-ibaea-qcaib-aeaqc-aibae-aqc","to_sol_address":"HS6NTv6GBVSLct8dsimRWRvjczJTAgfgDJt8VpR8wtGm","amount":"1_500_000_000","burn_id":0,"burn_timestamp":1711616761296437000,"icp_burn_block_index":106}"#
+        );
+        assert_eq!(
+            hex::encode(message_hash),
+            "a677d2b95a753183d5c5fe1329f20f4d71d753fd801248f263f7aa4fecd9b6e5"
+        );
+    }
+
+    #[test]
+    fn should_sign_whole_amount_without_fee() {
+        set_state(9, 1_000, 0);
+        let event = burned_event(Nat::from(100_000u64));
+
+        assert_eq!(event.get_fee(), Some(&Nat::from(0u8)));
+        assert_eq!(event.coupon_amount(), Nat::from(100_000u64));
+        assert_eq!(coupon_lamports(&event), "100_000");
+    }
+
+    #[test]
+    fn should_keep_fee_and_sign_the_rest() {
+        set_state(9, 1_000, 250);
+        let event = burned_event(Nat::from(100_000u64));
+
+        // the fee is recorded on the burn, so it stays auditable
+        assert_eq!(event.get_fee(), Some(&Nat::from(250u64)));
+        assert_eq!(event.coupon_amount(), Nat::from(99_750u64));
+        assert_eq!(coupon_lamports(&event), "99_750");
+    }
+
+    #[test]
+    fn should_reject_amount_below_minimum() {
+        set_state(9, 1_000, 250);
+
+        assert_eq!(
+            validate_withdrawal(SOLANA_ADDRESS, &Nat::from(999u64)),
+            Err(WithdrawError::AmountTooLow {
+                minimum: Nat::from(1_000u64),
+            })
+        );
+        validate_withdrawal(SOLANA_ADDRESS, &Nat::from(1_000u64)).unwrap();
+    }
+
+    #[test]
+    fn should_sign_deposited_lamports_with_six_decimals() {
+        set_state(6, 1_000, 0);
+
+        // the 6 decimal ledger drops the last 3 digits of the lamports
+        let minted = read_state(|s| s.lamports_to_gsol(&Nat::from(1_500_000_000u64)));
+        assert_eq!(minted, Nat::from(1_500_000u64));
+
+        validate_withdrawal(SOLANA_ADDRESS, &minted).unwrap();
+        assert_eq!(coupon_lamports(&burned_event(minted)), "1_500_000_000");
+    }
+
+    #[test]
+    fn should_sign_deposited_lamports_with_eighteen_decimals() {
+        set_state(18, 2_000_000_000, 1_000_000_000);
+
+        let minted = read_state(|s| s.lamports_to_gsol(&Nat::from(1_500_000_001u64)));
+        assert_eq!(minted, Nat::from(1_500_000_001_000_000_000u128));
+
+        validate_withdrawal(SOLANA_ADDRESS, &minted).unwrap();
+        // the fee of one lamport is kept by the minter
+        assert_eq!(coupon_lamports(&burned_event(minted)), "1_500_000_000");
+    }
+
+    #[test]
+    fn should_withdraw_whole_balance_minus_ledger_fee() {
+        set_state(9, 1_000, 0);
+
+        assert_eq!(
+            withdraw_all_amount(Nat::from(5_000_000u64), Nat::from(10_000u64)),
+            Ok(Nat::from(4_990_000u64))
+        );
+    }
+
+    #[test]
+    fn should_leave_dust_below_one_lamport_on_the_account() {
+        set_state(18, 2_000_000_000, 1_000_000_000);
+
+        let amount = withdraw_all_amount(
+            Nat::from(5_000_000_123_456_789_999u128),
+            Nat::from(10_000u64),
+        )
+        .unwrap();
+
+        assert_eq!(amount, Nat::from(5_000_000_123_000_000_000u128));
+        validate_withdrawal(SOLANA_ADDRESS, &amount).unwrap();
+    }
+
+    #[test]
+    fn should_reject_balance_not_covering_the_ledger_fee() {
+        set_state(9, 1_000, 0);
+
+        for balance in [0u64, 10_000] {
+            assert_eq!(
+                withdraw_all_amount(Nat::from(balance), Nat::from(10_000u64)),
+                Err(WithdrawError::NothingToWithdraw {
+                    balance: Nat::from(balance),
+                    fee: Nat::from(10_000u64),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn should_burn_from_the_specified_subaccount() {
+        test_fixtures::set_state(test_fixtures::state());
+        let subaccount = validate_subaccount(Some(vec![5; 32])).unwrap();
+        assert_eq!(subaccount, Some([5; 32]));
+
+        let event = burned_event(Nat::from(100_000u64));
+        let args = burn_args(&event, subaccount, test_fixtures::principal(0), 0);
+
+        assert_eq!(
+            args.from,
+            Account {
+                owner: test_fixtures::principal(1),
+                subaccount: Some([5; 32]),
+            }
+        );
+        assert_eq!(args.spender_subaccount, None);
+        assert_eq!(args.amount, Nat::from(100_000u64));
+    }
+
+    #[test]
+    fn should_reject_subaccount_of_invalid_length() {
+        assert_eq!(validate_subaccount(None), Ok(None));
+        assert_eq!(
+            validate_subaccount(Some(vec![5; 31])),
+            Err(WithdrawError::InvalidSubaccount(vec![5; 31]))
+        );
+    }
+
+    #[test]
+    fn should_collect_pending_burns_of_the_user_oldest_first() {
+        let mut state = test_fixtures::state();
+        let user = test_fixtures::principal(1);
+        for burn_id in (0..MAX_COUPONS_PER_CALL as u64 + 5).rev() {
+            // every third burn belongs to someone else
+            let from = match burn_id % 3 {
+                2 => test_fixtures::principal(2),
+                _ => user,
+            };
+            let mut event = WithdrawalEvent::new(
+                burn_id,
+                from,
+                SOLANA_ADDRESS.to_string(),
+                Nat::from(100_000u64),
+                Nat::from(0u8),
+            );
+            event.update_after_burn(1_711_616_761_296_437_000, burn_id);
+            state.record_or_retry_withdrawal_burned_event(event);
+        }
+
+        let pending = pending_coupon_burn_ids(&state, user);
+
+        assert_eq!(pending, vec![0, 1, 3, 4, 6, 7, 9, 10, 12, 13]);
+        assert_eq!(pending.len(), MAX_COUPONS_PER_CALL);
+        assert!(pending_coupon_burn_ids(&state, test_fixtures::principal(3)).is_empty());
+    }
+
+    #[test]
+    fn should_recover_burn_id_from_errors_after_the_burn() {
+        let signing = WithdrawError::SigningWithEcdsaFailed {
+            burn_id: 3,
+            code: RejectionCode::CanisterReject,
+            msg: "signing rejected".to_string(),
+        };
+        let coupon = WithdrawError::CouponError {
+            burn_id: 4,
+            err: CouponError::RecoveryError,
+        };
+
+        assert_eq!(signing.burn_id(), Some(3));
+        assert_eq!(coupon.burn_id(), Some(4));
+        // nothing was burned yet
+        assert_eq!(
+            WithdrawError::AmountTooLow {
+                minimum: Nat::from(1u8)
+            }
+            .burn_id(),
+            None
+        );
+    }
+
+    #[test]
+    fn should_keep_burn_after_failed_signing() {
+        test_fixtures::set_state(test_fixtures::state());
+        let event = burned_event(Nat::from(100_000u64));
+        // a failed signing records the burned event again, counting a retry
+        mutate_state(|s| s.record_or_retry_withdrawal_burned_event(event.clone()));
+        mutate_state(|s| s.record_or_retry_withdrawal_burned_event(event.clone()));
+
+        let (message, _) = event.coupon_message(CouponHashAlgo::Sha256);
+        assert_eq!(get_coupon_message(0).unwrap().message, message);
+        assert_eq!(
+            read_state(|s| s.withdrawal_burned_events[&0].retry.get_retries()),
+            1
+        );
+    }
+
+    #[test]
+    fn should_split_signature_into_r_s_v() {
+        test_fixtures::set_state(test_fixtures::state());
+        let mut coupon = coupon(&burned_event(Nat::from(100_000u64)));
+        let signature: Vec<u8> = (0..64).collect();
+        coupon.signature_hex = hex::encode(&signature);
+
+        let split = coupon.split_signature().unwrap();
+
+        assert_eq!(format!("{}{}", split.r, split.s), coupon.signature_hex);
+        assert_eq!(hex::decode(&split.r).unwrap(), signature[..32]);
+        assert_eq!(split.v, 1);
+        assert_eq!(split.message_hash, coupon.message_hash);
+        assert_eq!(split.public_key, coupon.icp_public_key_hex);
+    }
+
+    #[test]
+    fn should_not_split_malformed_signatures() {
+        test_fixtures::set_state(test_fixtures::state());
+        let coupon = coupon(&burned_event(Nat::from(100_000u64)));
+
+        let short = Coupon {
+            signature_hex: "ab".repeat(63),
+            ..coupon.clone()
+        };
+        assert_eq!(
+            short.split_signature(),
+            Err(CouponError::DeserializationError)
+        );
+        let without_recovery_id = Coupon {
+            recovery_id: None,
+            ..coupon
+        };
+        assert_eq!(
+            without_recovery_id.split_signature(),
+            Err(CouponError::MissingRecoveryId)
+        );
+    }
+
+    #[test]
+    fn should_return_stored_coupon_without_signing_again() {
+        test_fixtures::set_state(test_fixtures::state());
+        let mut event = burned_event(Nat::from(100_000u64));
+        mutate_state(|s| s.record_or_retry_withdrawal_burned_event(event.clone()));
+        let signed = coupon(&event);
+        event.update_after_redeem(signed.clone());
+        mutate_state(|s| s.record_withdrawal_redeemed_event(event));
+
+        // signing calls the management canister, which would panic outside of a canister
+        for _ in 0..2 {
+            assert_eq!(
+                futures::executor::block_on(get_coupon(0)),
+                Ok(signed.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn should_reject_concurrent_coupon_generation_for_the_same_burn_id() {
+        test_fixtures::set_state(test_fixtures::state());
+        mutate_state(|s| {
+            s.record_or_retry_withdrawal_burned_event(burned_event(Nat::from(100_000u64)))
+        });
+
+        // the first call is suspended while signing, holding the guard of the burn
+        let first = retrieve_coupon_guard(0).unwrap();
+        let second = futures::executor::block_on(get_coupon(0));
+        assert_eq!(second, Err(WithdrawError::AlreadyProcessing { burn_id: 0 }));
+        assert_eq!(second.unwrap_err().burn_id(), Some(0));
+
+        // other burns are not blocked, and the burn is released with the guard
+        assert!(retrieve_coupon_guard(1).is_ok());
+        drop(first);
+        assert!(retrieve_coupon_guard(0).is_ok());
+    }
+
+    #[test]
+    fn should_reject_amount_below_one_lamport_with_eighteen_decimals() {
+        set_state(18, 1_000_000_000, 0);
+
+        let amount = Nat::from(1_500_000_001_000_000_001u128);
+        assert_eq!(
+            validate_withdrawal(SOLANA_ADDRESS, &amount),
+            Err(WithdrawError::InexactLamportAmount {
+                amount,
+                granularity: Nat::from(1_000_000_000u64),
+            })
+        );
+    }
+
+    // Signs like the management canister would, with the key of `test_fixtures::ecdsa_public_key`.
+    async fn sign_locally(message_hash: Vec<u8>) -> Result<Vec<u8>, (RejectionCode, String)> {
+        let secret_key = libsecp256k1::SecretKey::parse(&[7; 32]).unwrap();
+        let message = libsecp256k1::Message::parse_slice(&message_hash).unwrap();
+        let (signature, _) = libsecp256k1::sign(&message, &secret_key);
+        Ok(signature.serialize().to_vec())
+    }
+
+    fn uncompressed_public_key_hex(secret: u8) -> String {
+        let secret_key = libsecp256k1::SecretKey::parse(&[secret; 32]).unwrap();
+        hex::encode(libsecp256k1::PublicKey::from_secret_key(&secret_key).serialize())
+    }
+
+    #[test]
+    fn should_pass_self_test_with_a_local_signer() {
+        for hash_algo in [CouponHashAlgo::Keccak256, CouponHashAlgo::Sha256] {
+            let report = futures::executor::block_on(run_self_test(
+                hash_algo,
+                uncompressed_public_key_hex(7),
+                sign_locally,
+            ));
+            assert_eq!(
+                report,
+                SelfTestReport {
+                    signed: true,
+                    parity_recovered: true,
+                    verified: true,
+                    error: None,
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn should_report_the_failing_self_test_step() {
+        let rejected = futures::executor::block_on(run_self_test(
+            CouponHashAlgo::Sha256,
+            uncompressed_public_key_hex(7),
+            |_| async { Err((RejectionCode::CanisterError, "no key".to_string())) },
+        ));
+        assert!(!rejected.signed);
+        assert_eq!(
+            rejected.error,
+            Some("signing failed: CanisterError no key".to_string())
+        );
+
+        // a signature from another key recovers to neither parity
+        let wrong_key = futures::executor::block_on(run_self_test(
+            CouponHashAlgo::Sha256,
+            uncompressed_public_key_hex(8),
+            sign_locally,
+        ));
+        assert!(wrong_key.signed);
+        assert!(!wrong_key.parity_recovered);
+        assert!(!wrong_key.verified);
+        assert!(wrong_key.error.is_some());
+    }
+
+    #[test]
+    fn should_return_burn_events_after_the_timestamp_oldest_first() {
+        test_fixtures::set_state(test_fixtures::state());
+        mutate_state(|s| {
+            for (burn_id, timestamp) in [(0, 300), (1, 100), (2, 200), (3, 250)] {
+                s.record_or_retry_withdrawal_burned_event(burned_at(burn_id, timestamp));
+            }
+            let mut redeemed = burned_at(3, 250);
+            redeemed.update_after_redeem(coupon(&redeemed));
+            s.record_withdrawal_redeemed_event(redeemed);
+        });
+
+        // the event burned exactly at the timestamp was already seen
+        let page = get_burn_events_since(200, None);
+        let returned: Vec<(u64, Option<u64>, bool)> = page
+            .events
+            .iter()
+            .map(|e| {
+                (
+                    e.withdrawal.burn_id,
+                    e.withdrawal.burn_timestamp,
+                    e.redeemed,
+                )
+            })
+            .collect();
+        assert_eq!(returned, vec![(3, Some(250), true), (0, Some(300), false)]);
+        assert!(!page.has_more);
+
+        assert!(get_burn_events_since(300, None).events.is_empty());
+    }
+
+    #[test]
+    fn should_continue_burn_events_inside_a_group_of_equal_timestamps() {
+        test_fixtures::set_state(test_fixtures::state());
+        // burns executed in the same round share the timestamp, the page ends inside the group
+        mutate_state(|s| {
+            for burn_id in 0..=MAX_BURN_EVENTS_PAGE_SIZE as u64 + 1 {
+                s.record_or_retry_withdrawal_burned_event(burned_at(burn_id, 1_000));
+            }
+        });
+
+        let page = get_burn_events_since(0, None);
+        assert!(page.has_more);
+        let last = &page.events.last().unwrap().withdrawal;
+        assert_eq!(last.burn_id, MAX_BURN_EVENTS_PAGE_SIZE as u64 - 1);
+
+        // the timestamp alone would skip the rest of the group
+        assert!(get_burn_events_since(1_000, None).events.is_empty());
+
+        let next = get_burn_events_since(1_000, Some(last.burn_id));
+        assert!(!next.has_more);
+        let burn_ids: Vec<u64> = next.events.iter().map(|e| e.withdrawal.burn_id).collect();
+        assert_eq!(
+            burn_ids,
+            vec![
+                MAX_BURN_EVENTS_PAGE_SIZE as u64,
+                MAX_BURN_EVENTS_PAGE_SIZE as u64 + 1
+            ]
+        );
+    }
+
+    #[test]
+    fn should_bound_burn_events_since_the_timestamp() {
+        test_fixtures::set_state(test_fixtures::state());
+        mutate_state(|s| {
+            for burn_id in 0..=MAX_BURN_EVENTS_PAGE_SIZE as u64 {
+                s.record_or_retry_withdrawal_burned_event(burned_at(burn_id, 1_000 + burn_id));
+            }
+        });
+
+        let page = get_burn_events_since(0, None);
+        assert!(page.has_more);
+        assert_eq!(page.events.len(), MAX_BURN_EVENTS_PAGE_SIZE);
+
+        // continuing from the last returned event yields the rest
+        let last = &page.events.last().unwrap().withdrawal;
+        let next = get_burn_events_since(last.burn_timestamp.unwrap(), Some(last.burn_id));
+        assert!(!next.has_more);
+        assert_eq!(next.events.len(), 1);
+        assert_eq!(
+            next.events[0].withdrawal.burn_id,
+            MAX_BURN_EVENTS_PAGE_SIZE as u64
+        );
+    }
+
+    #[test]
+    fn should_recover_the_parity_of_the_signing_key() {
+        let secret_key = libsecp256k1::SecretKey::parse(&[7; 32]).unwrap();
+        let public_key = libsecp256k1::PublicKey::from_secret_key(&secret_key).serialize();
+        let key = VerifyingKey::from_sec1_bytes(&public_key).unwrap();
+        let other_key =
+            VerifyingKey::from_sec1_bytes(&hex::decode(uncompressed_public_key_hex(8)).unwrap())
+                .unwrap();
+
+        // both parities occur over this many messages
+        for i in 0u32..64 {
+            let message_hash = Sha256::digest(i.to_be_bytes()).to_vec();
+            let message = libsecp256k1::Message::parse_slice(&message_hash).unwrap();
+            let (signature, recovery_id) = libsecp256k1::sign(&message, &secret_key);
+            let signature = Signature::try_from(signature.serialize().as_slice()).unwrap();
+
+            assert_eq!(
+                recover_parity(&message_hash, &signature, &key),
+                Some(recovery_id.serialize())
+            );
+            assert_eq!(recover_parity(&message_hash, &signature, &other_key), None);
+        }
+    }
+
+    #[test]
+    fn should_produce_verifiable_coupons_with_both_hash_algorithms() {
+        test_fixtures::set_state(test_fixtures::state());
+        let event = burned_event(Nat::from(100_000u64));
+
+        for hash_algo in [CouponHashAlgo::Sha256, CouponHashAlgo::Keccak256] {
+            let (message, message_hash) = event.coupon_message(hash_algo);
+            let signature =
+                futures::executor::block_on(sign_locally(message_hash.clone())).unwrap();
+            let mut coupon = Coupon::new(
+                message,
+                hex::encode(message_hash),
+                hex::encode(signature),
+                uncompressed_public_key_hex(7),
+                hash_algo,
+            );
+
+            assert!(coupon.y_parity().is_ok());
+            assert_eq!(coupon.verify(), Ok(true));
+
+            // the recorded algorithm is the one the signature is re-derived with
+            let other_algo = match hash_algo {
+                CouponHashAlgo::Sha256 => CouponHashAlgo::Keccak256,
+                CouponHashAlgo::Keccak256 => CouponHashAlgo::Sha256,
+            };
+            let mislabeled = Coupon {
+                hash_algo: Some(other_algo),
+                ..coupon
+            };
+            assert_eq!(mislabeled.verify(), Ok(false));
+        }
+    }
+
+    #[test]
+    fn should_answer_a_second_verification_from_the_cache() {
+        test_fixtures::set_state(test_fixtures::state());
+        VERIFIED_COUPONS.with(|cache| cache.borrow_mut().clear());
+        let event = burned_event(Nat::from(100_000u64));
+        let (message, message_hash) = event.coupon_message(CouponHashAlgo::Sha256);
+        let signature = futures::executor::block_on(sign_locally(message_hash.clone())).unwrap();
+        let mut coupon = Coupon::new(
+            message,
+            hex::encode(message_hash),
+            hex::encode(signature),
+            uncompressed_public_key_hex(7),
+            CouponHashAlgo::Sha256,
+        );
+        coupon.y_parity().unwrap();
+
+        assert_eq!(coupon.verify(), Ok(true));
+        assert_eq!(VERIFIED_COUPONS.with(|cache| cache.borrow().len()), 1);
+
+        // a recomputation would return true, the tampered entry shows the cache is read
+        VERIFIED_COUPONS.with(|cache| cache.borrow_mut()[0].1 = false);
+        assert_eq!(coupon.verify(), Ok(false));
+        assert_eq!(VERIFIED_COUPONS.with(|cache| cache.borrow().len()), 1);
+    }
+
+    #[test]
+    fn should_return_stored_coupons_without_recovering_parity() {
+        test_fixtures::set_state(test_fixtures::state());
+        let user = test_fixtures::principal(1);
+        let redeemed = burned_at(0, 100);
+        let stored = coupon(&redeemed);
+        mutate_state(|s| {
+            s.record_or_retry_withdrawal_burned_event(redeemed.clone());
+            let mut redeemed = redeemed;
+            redeemed.update_after_redeem(stored.clone());
+            s.record_withdrawal_redeemed_event(redeemed);
+            s.record_or_retry_withdrawal_burned_event(burned_at(1, 200));
+        });
+        VERIFIED_COUPONS.with(|cache| cache.borrow_mut().clear());
+
+        // the stored signature is fake, recovering its parity or verifying it would fail
+        let info = futures::executor::block_on(get_withdraw_info(user));
+
+        assert_eq!(
+            info,
+            UserWithdrawInfo {
+                coupons: vec![stored],
+                burn_ids: vec![1],
+            }
+        );
+        assert!(VERIFIED_COUPONS.with(|cache| cache.borrow().is_empty()));
+    }
+
+    // Runs the retry loop against the given responses of the management canister, returns the
+    // result and the number of attempts.
+    fn sign_with_responses(
+        responses: Vec<Result<u8, (RejectionCode, String)>>,
+    ) -> (Result<u8, (RejectionCode, String)>, usize) {
+        let responses = RefCell::new(VecDeque::from(responses));
+        let attempts = std::cell::Cell::new(0);
+        let result = futures::executor::block_on(retry_transient_rejections(
+            || {
+                attempts.set(attempts.get() + 1);
+                let response = responses.borrow_mut().pop_front().unwrap();
+                async move { response }
+            },
+            |_| async {},
+        ));
+        (result, attempts.get())
+    }
+
+    fn transient() -> Result<u8, (RejectionCode, String)> {
+        Err((RejectionCode::SysTransient, "busy".to_string()))
+    }
+
+    #[test]
+    fn should_retry_transient_signing_rejections() {
+        assert_eq!(
+            sign_with_responses(vec![transient(), transient(), Ok(1)]),
+            (Ok(1), 3)
+        );
+    }
+
+    #[test]
+    fn should_back_off_exponentially_between_signing_attempts() {
+        let responses = RefCell::new(VecDeque::from(vec![transient(), transient(), Ok(1)]));
+        let delays = RefCell::new(vec![]);
+
+        let result = futures::executor::block_on(retry_transient_rejections(
+            || {
+                let response = responses.borrow_mut().pop_front().unwrap();
+                async move { response }
+            },
+            |delay| {
+                delays.borrow_mut().push(delay);
+                async {}
+            },
+        ));
+
+        assert_eq!(result, Ok(1));
+        assert_eq!(
+            delays.into_inner(),
+            vec![
+                SIGN_WITH_ECDSA_RETRY_BACKOFF,
+                SIGN_WITH_ECDSA_RETRY_BACKOFF * 2
+            ]
+        );
+    }
+
+    #[test]
+    fn should_give_up_signing_after_the_retry_limit() {
+        let limit = SIGN_WITH_ECDSA_RETRY_LIMIT as usize;
+        let mut responses = vec![transient(); limit + 1];
+        responses.push(Ok(1));
+
+        assert_eq!(sign_with_responses(responses), (transient(), limit + 1));
+    }
+
+    #[test]
+    fn should_not_retry_permanent_signing_rejections() {
+        let rejected = Err((
+            RejectionCode::CanisterReject,
+            "not enough cycles".to_string(),
+        ));
+
+        assert_eq!(
+            sign_with_responses(vec![rejected.clone(), Ok(1)]),
+            (rejected, 1)
+        );
+    }
+
+    #[test]
+    fn should_find_withdrawals_by_solana_address() {
+        use crate::test_fixtures::SOLANA_CONTRACT_ADDRESS;
+
+        test_fixtures::set_state(test_fixtures::state());
+        let to = |burn_id: u64, address: &str| {
+            let mut event = burned_at(burn_id, 100 + burn_id);
+            event.to_sol_address = address.to_string();
+            event
+        };
+        mutate_state(|s| {
+            s.record_or_retry_withdrawal_burned_event(to(0, SOLANA_ADDRESS));
+            s.record_or_retry_withdrawal_burned_event(to(1, SOLANA_CONTRACT_ADDRESS));
+            s.record_or_retry_withdrawal_burned_event(to(2, SOLANA_ADDRESS));
+            let mut redeemed = to(2, SOLANA_ADDRESS);
+            redeemed.update_after_redeem(coupon(&redeemed));
+            s.record_withdrawal_redeemed_event(redeemed);
+        });
+
+        let found = find_withdrawals_by_sol_address(SOLANA_ADDRESS);
+        let statuses: Vec<(u64, bool)> = found
+            .events
+            .iter()
+            .map(|e| (e.withdrawal.burn_id, e.redeemed))
+            .collect();
+        assert_eq!(statuses, vec![(0, false), (2, true)]);
+        assert!(!found.has_more);
+
+        let other = find_withdrawals_by_sol_address(SOLANA_CONTRACT_ADDRESS);
+        assert_eq!(other.events.len(), 1);
+        assert_eq!(other.events[0].withdrawal.burn_id, 1);
+        assert!(find_withdrawals_by_sol_address("unknown").events.is_empty());
+    }
+
+    #[test]
+    fn should_expose_the_recorded_redemption_signature() {
+        use crate::state::audit::apply_state_transition;
+
+        test_fixtures::set_state(test_fixtures::state());
+        mutate_state(|s| {
+            let mut redeemed = burned_at(0, 100);
+            s.record_or_retry_withdrawal_burned_event(redeemed.clone());
+            redeemed.update_after_redeem(coupon(&redeemed));
+            s.record_withdrawal_redeemed_event(redeemed);
+        });
+        let redemption_sol_sig =
+            || get_withdrawal_by_burn_id(0).map(|e| e.withdrawal.redemption_sol_sig);
+        assert_eq!(redemption_sol_sig(), Some(None));
+
+        mutate_state(|s| {
+            apply_state_transition(
+                s,
+                &EventType::RedemptionSignature {
+                    burn_id: 0,
+                    sol_sig: "redemption".to_string(),
+                },
+            )
+        });
+
+        assert_eq!(redemption_sol_sig(), Some(Some("redemption".to_string())));
+        assert_eq!(get_withdrawal_by_burn_id(1), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "NON redeemed withdrawal")]
+    fn should_not_record_redemption_of_a_withdrawal_without_coupon() {
+        let mut state = test_fixtures::state();
+        state.record_or_retry_withdrawal_burned_event(burned_at(0, 100));
+
+        state.record_redemption_signature(0, "redemption");
+    }
+}